@@ -0,0 +1,23 @@
+use std::{env, fs, path::Path};
+
+// lists the monster, wall and mark templates in assets/ for recognition.rs to build in,
+// there can be any number of them so they can't be spelled out like the ground ones
+fn main() {
+    println!("cargo:rerun-if-changed=assets");
+    let mut code = String::new();
+    for (name, dir) in [("MONSTERS", "monsters"), ("WALLS", "walls"), ("MARKS", "marks")] {
+        let mut paths: Vec<_> = fs::read_dir(Path::new("assets").join(dir))
+            .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+            .unwrap_or_default();
+        paths.retain(|path| path.extension().is_some_and(|extension| extension == "png"));
+        paths.sort();
+        code += &format!("const {name}: &[(&str, &[u8])] = &[\n");
+        for path in paths {
+            let file = format!("{dir}/{}", path.file_name().unwrap().to_string_lossy());
+            let path = fs::canonicalize(&path).unwrap();
+            code += &format!("    ({file:?}, include_bytes!({:?})),\n", path.to_string_lossy());
+        }
+        code += "];\n";
+    }
+    fs::write(Path::new(&env::var("OUT_DIR").unwrap()).join("templates.rs"), code).unwrap();
+}
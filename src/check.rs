@@ -0,0 +1,87 @@
+use std::fmt;
+
+use crate::{check_size, contradiction::{Contradiction, Position}, find_contradiction, options::SolveOptions, solve_all, Tile};
+
+// more than enough for any real puzzle, keeps a badly underconstrained board from running forever
+pub const SOLUTION_LIMIT: usize = 1000;
+
+// a mark no solution agrees with
+pub struct Mistake {
+    pub x: usize,
+    pub y: usize,
+    // what the player put there, every solution has the other one
+    pub marked: Tile,
+    // rule broken by this mark together with the player's correct marks,
+    // none if it only rules out every solution without breaking anything yet
+    pub broken: Option<Contradiction>,
+}
+
+impl fmt::Display for Mistake {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let position = Position(self.x, self.y);
+        if self.marked == Tile::Wall {
+            write!(f, "the wall at {position} should be open")?;
+        } else {
+            write!(f, "the open cell at {position} should be a wall")?;
+        }
+        match &self.broken {
+            Some(contradiction) => write!(f, ", it breaks a rule right away: {contradiction}"),
+            None => write!(f, ", it doesn't break a rule yet but rules out every solution"),
+        }
+    }
+}
+
+pub struct CheckReport {
+    // solutions of the puzzle without the player's marks
+    pub solutions: usize,
+    // hit SOLUTION_LIMIT, so some marks might only look wrong
+    pub capped: bool,
+    pub mistakes: Vec<Mistake>,
+}
+
+// compares the walls and ground the player placed against every solution of the puzzle
+pub fn check(board: &[Vec<Tile>], nums_columns: &[usize], nums_rows: &[usize]) -> Result<CheckReport, String> {
+    check_size(board, nums_columns, nums_rows)?;
+    let puzzle: Vec<Vec<Tile>> = board.iter().map(|row| {
+        row.iter().map(|tile| match tile {
+            Tile::Wall | Tile::Ground => Tile::Unsure,
+            tile => tile.clone(),
+        }).collect()
+    }).collect();
+    let solutions = solve_all(&puzzle, nums_columns, nums_rows, &SolveOptions::default(), SOLUTION_LIMIT);
+
+    // marks that some solution agrees with
+    let agrees = |x: usize, y: usize| solutions.iter().any(|solution| solution[y][x] == board[y][x]);
+    let mut correct = puzzle.clone();
+    let mut wrong = vec![];
+    for y in 0..8 {
+        for x in 0..8 {
+            if matches!(board[y][x], Tile::Wall | Tile::Ground) {
+                if agrees(x, y) {
+                    correct[y][x] = board[y][x].clone();
+                } else {
+                    wrong.push((x, y));
+                }
+            }
+        }
+    }
+
+    let mut mistakes = vec![];
+    if !solutions.is_empty() {
+        for (x, y) in wrong {
+            let mut matrix = correct.clone();
+            matrix[y][x] = board[y][x].clone();
+            mistakes.push(Mistake {
+                x,
+                y,
+                marked: board[y][x].clone(),
+                broken: find_contradiction(&matrix, nums_columns, nums_rows),
+            });
+        }
+    }
+    Ok(CheckReport {
+        capped: solutions.len() >= SOLUTION_LIMIT,
+        solutions: solutions.len(),
+        mistakes,
+    })
+}
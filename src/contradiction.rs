@@ -0,0 +1,89 @@
+use std::fmt;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Line {
+    Row(usize),
+    Column(usize),
+}
+
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Line::Row(i) => write!(f, "row {}", i + 1),
+            Line::Column(i) => write!(f, "column {}", i + 1),
+        }
+    }
+}
+
+// a rule broken by a matrix, coordinates point at the tile that gave it away
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Contradiction {
+    // 2x2 ground not part of a treasure room, x and y are its top left tile
+    OpenArea { x: usize, y: usize },
+    NoTreasureRoom { x: usize, y: usize },
+    MonsterNotInDeadEnd { x: usize, y: usize },
+    MonsterWalledIn { x: usize, y: usize },
+    DeadEndWithoutMonster { x: usize, y: usize },
+    TooManyWalls { line: Line, needed: usize, walls: usize },
+    TooFewWalls { line: Line, needed: usize, walls: usize, unsure: usize },
+    // ground that can't be reached from the rest of the ground
+    Disconnected { x: usize, y: usize },
+    // the board or its clues aren't 8x8
+    WrongSize,
+}
+
+impl Contradiction {
+    pub const CATEGORIES: [&'static str; 9] = [
+        "open area",
+        "no treasure room",
+        "monster not in dead end",
+        "monster walled in",
+        "dead end without monster",
+        "too many walls",
+        "too few walls",
+        "disconnected",
+        "wrong size",
+    ];
+
+    // index into CATEGORIES
+    pub fn category(&self) -> usize {
+        match self {
+            Contradiction::OpenArea { .. } => 0,
+            Contradiction::NoTreasureRoom { .. } => 1,
+            Contradiction::MonsterNotInDeadEnd { .. } => 2,
+            Contradiction::MonsterWalledIn { .. } => 3,
+            Contradiction::DeadEndWithoutMonster { .. } => 4,
+            Contradiction::TooManyWalls { .. } => 5,
+            Contradiction::TooFewWalls { .. } => 6,
+            Contradiction::Disconnected { .. } => 7,
+            Contradiction::WrongSize => 8,
+        }
+    }
+}
+
+impl fmt::Display for Contradiction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Contradiction::OpenArea { x, y } => write!(f, "{} is part of a 2x2 open area outside any treasure room", Position(x, y)),
+            Contradiction::NoTreasureRoom { x, y } => write!(f, "the chest at {} has nowhere to put its treasure room", Position(x, y)),
+            Contradiction::MonsterNotInDeadEnd { x, y } => write!(f, "the monster at {} isn't in a dead end", Position(x, y)),
+            Contradiction::MonsterWalledIn { x, y } => write!(f, "the monster at {} is walled in", Position(x, y)),
+            Contradiction::DeadEndWithoutMonster { x, y } => write!(f, "{} is a dead end without a monster", Position(x, y)),
+            Contradiction::TooManyWalls { line, needed, walls } => write!(f, "{line} needs {needed} walls but already has {walls}"),
+            Contradiction::TooFewWalls { line, needed, walls, unsure } => {
+                write!(f, "{line} needs {needed} walls but has {walls} and only {unsure} unknown cells remain")
+            },
+            Contradiction::Disconnected { x, y } => write!(f, "{} is cut off from the rest of the dungeon", Position(x, y)),
+            Contradiction::WrongSize => write!(f, "the board isn't 8x8 with 8 clues a side"),
+        }
+    }
+}
+
+// column and row of a tile, counted from 1 like a player would
+pub struct Position(pub usize, pub usize);
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "column {}, row {}", self.0 + 1, self.1 + 1)
+    }
+}
@@ -0,0 +1,143 @@
+use std::fmt;
+
+use crate::{contradiction::{Contradiction, Position}, find_contradiction, options::SolveOptions, settle, solve, SolveResult, Tile, ANY_COUNT};
+
+// matrices each what-if solve gets to expand before it counts as inconclusive
+const PROBE_NODE_LIMIT: u64 = 300;
+
+// a piece of the recognised puzzle that might be wrong
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Culprit {
+    RowClue { row: usize, clue: usize },
+    ColumnClue { column: usize, clue: usize },
+    Monster { x: usize, y: usize },
+    Chest { x: usize, y: usize },
+}
+
+impl fmt::Display for Culprit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Culprit::RowClue { row, clue } => write!(f, "the {clue} on row {}", row + 1),
+            Culprit::ColumnClue { column, clue } => write!(f, "the {clue} on column {}", column + 1),
+            Culprit::Monster { x, y } => write!(f, "the monster at {}", Position(x, y)),
+            Culprit::Chest { x, y } => write!(f, "the chest at {}", Position(x, y)),
+        }
+    }
+}
+
+pub struct Explanation {
+    // rule that breaks as soon as everything certain is collapsed, if there is one
+    pub contradiction: Option<Contradiction>,
+    // clues, monsters and chests that can't all be right at the same time
+    pub culprits: Vec<Culprit>,
+    // some clue was let go of without knowing whether the puzzle stays unsolvable without it,
+    // so the culprits are a best guess rather than a set that's been shown to conflict
+    pub guessed: bool,
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(contradiction) = &self.contradiction {
+            writeln!(f, "{contradiction}")?;
+        }
+        if self.guessed {
+            writeln!(f, "these probably can't all be right, not every check finished in time:")?;
+        } else {
+            writeln!(f, "these can't all be right:")?;
+        }
+        for culprit in self.culprits.iter() {
+            writeln!(f, "  {culprit}")?;
+        }
+        Ok(())
+    }
+}
+
+// works out why a puzzle has no solution
+//
+// starts out blaming every clue and lets go of each one the puzzle stays unsolvable
+// without, what's left is usually the misread part. monsters and chests get blamed
+// if the puzzle works out without them, since one might not really be there.
+pub fn explain(matrix: &[Vec<Tile>], nums_columns: &[usize], nums_rows: &[usize], options: &SolveOptions) -> Explanation {
+    // the rules picked for the solve, but a budget of its own, the solve's deadline has
+    // usually passed by the time anyone asks why it failed
+    let options = SolveOptions {
+        node_limit: Some(PROBE_NODE_LIMIT),
+        deadline: None,
+        observer: None,
+        tree: None,
+        ..options.clone()
+    };
+
+    let mut settled = matrix.to_vec();
+    let contradiction = if settle(&mut settled, nums_columns, nums_rows, &options) {
+        None
+    } else {
+        find_contradiction(&settled, nums_columns, nums_rows)
+    };
+
+    // dropping a clue only ever makes a puzzle easier, so whatever clues are left at the end are needed.
+    // a clue goes unless the puzzle is known to work without it, otherwise every probe that runs out
+    // of budget keeps one more clue and the list stops pointing at anything. that makes the list a
+    // guess though, and it says so
+    let mut guessed = false;
+    let mut culprits = vec![];
+    for (column, &clue) in nums_columns.iter().enumerate() {
+        culprits.push(Culprit::ColumnClue { column, clue });
+    }
+    for (row, &clue) in nums_rows.iter().enumerate() {
+        culprits.push(Culprit::RowClue { row, clue });
+    }
+    let mut i = 0;
+    while i < culprits.len() {
+        let mut remaining = culprits.clone();
+        remaining.remove(i);
+        let nums_columns = clues_kept(nums_columns, &remaining, |column, clue| Culprit::ColumnClue { column, clue });
+        let nums_rows = clues_kept(nums_rows, &remaining, |row, clue| Culprit::RowClue { row, clue });
+        match probe(matrix.to_vec(), &nums_columns, &nums_rows, &options) {
+            SolveResult::Solved(_) => i += 1,
+            SolveResult::Unsolvable => culprits = remaining,
+            SolveResult::Exhausted(_) => {
+                guessed = true;
+                culprits = remaining;
+            },
+        }
+    }
+
+    // a monster or chest that the puzzle works without might have been misread too
+    for (y, row) in matrix.iter().enumerate() {
+        for (x, tile) in row.iter().enumerate() {
+            let culprit = match tile {
+                Tile::Monster => Culprit::Monster { x, y },
+                Tile::Chest => Culprit::Chest { x, y },
+                _ => continue,
+            };
+            let mut without = matrix.to_vec();
+            without[y][x] = Tile::Unsure;
+            if is_solvable(without, nums_columns, nums_rows, &options) {
+                culprits.push(culprit);
+            }
+        }
+    }
+
+    Explanation {
+        contradiction,
+        culprits,
+        guessed,
+    }
+}
+
+// clues left out of the culprits get replaced with one that fits any number of walls
+fn clues_kept(clues: &[usize], culprits: &[Culprit], culprit: impl Fn(usize, usize) -> Culprit) -> Vec<usize> {
+    clues.iter().enumerate().map(|(i, &clue)| {
+        if culprits.contains(&culprit(i, clue)) { clue } else { ANY_COUNT }
+    }).collect()
+}
+
+// running out of budget counts as neither solvable nor unsolvable
+fn probe(matrix: Vec<Vec<Tile>>, nums_columns: &[usize], nums_rows: &[usize], options: &SolveOptions) -> SolveResult {
+    solve(&matrix, nums_columns, nums_rows, options).result
+}
+
+fn is_solvable(matrix: Vec<Vec<Tile>>, nums_columns: &[usize], nums_rows: &[usize], options: &SolveOptions) -> bool {
+    matches!(probe(matrix, nums_columns, nums_rows, options), SolveResult::Solved(_))
+}
@@ -0,0 +1,292 @@
+use std::{cmp::Reverse, collections::HashMap, fs, hash::Hash};
+
+use screenshots::image::RgbaImage;
+
+use crate::recognition::close;
+
+// size of the crop from the middle of a tile that gets compared against the templates
+pub const CAPTURE_SIZE: i32 = 8;
+
+// where calibration saves the layout and later runs look for it
+pub const PROFILE: &str = "layout.profile";
+
+// anything matching a template less than this is too far off to be worth mentioning
+const REPORT_BELOW: f32 = 0.5;
+
+// where the board sits on the screen, in pixels
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Layout {
+    // top left corner of the top left tile
+    pub tile_x: i32,
+    pub tile_y: i32,
+    // distance from one tile to the next, tiles are square
+    pub tile_size: i32,
+    // top left corner of the first clue crop of each kind, relative to tile_x, tile_y,
+    // every clue crop is tile_size across
+    pub column_clues: (i32, i32),
+    pub row_clues: (i32, i32),
+}
+
+impl Layout {
+    // the 1920x1080 window the templates were taken from, calibration scales the clue offsets from this
+    pub const REFERENCE: Layout = Layout {
+        tile_x: 722,
+        tile_y: 428,
+        tile_size: 66,
+        column_clues: (8, -6 - 66),
+        row_clues: (-66, 0),
+    };
+
+    // top left corner of the crop from the middle of a tile
+    pub fn tile_crop(&self, x: usize, y: usize) -> (i32, i32) {
+        (
+            self.tile_x + (self.tile_size - CAPTURE_SIZE) / 2 + x as i32 * self.tile_size,
+            self.tile_y + (self.tile_size - CAPTURE_SIZE) / 2 + y as i32 * self.tile_size,
+        )
+    }
+
+    pub fn tile_center(&self, x: usize, y: usize) -> (i32, i32) {
+        (
+            self.tile_x + x as i32 * self.tile_size + self.tile_size / 2,
+            self.tile_y + y as i32 * self.tile_size + self.tile_size / 2,
+        )
+    }
+
+    // top left corner of the clue above column i
+    pub fn column_clue(&self, i: usize) -> (i32, i32) {
+        (
+            self.tile_x + self.column_clues.0 + i as i32 * self.tile_size,
+            self.tile_y + self.column_clues.1,
+        )
+    }
+
+    // top left corner of the clue left of row i
+    pub fn row_clue(&self, i: usize) -> (i32, i32) {
+        (
+            self.tile_x + self.row_clues.0,
+            self.tile_y + self.row_clues.1 + i as i32 * self.tile_size,
+        )
+    }
+
+    // top left corner and size of the smallest area with every tile and clue in it
+    pub fn bounds(&self) -> ((i32, i32), (i32, i32)) {
+        let board = 8 * self.tile_size;
+        let left = self.tile_x.min(self.tile_x + self.row_clues.0).min(self.tile_x + self.column_clues.0);
+        let top = self.tile_y.min(self.tile_y + self.row_clues.1).min(self.tile_y + self.column_clues.1);
+        let right = (self.tile_x + board).max(self.tile_x + self.row_clues.0 + self.tile_size).max(self.tile_x + self.column_clues.0 + board);
+        let bottom = (self.tile_y + board).max(self.tile_y + self.row_clues.1 + board).max(self.tile_y + self.column_clues.1 + self.tile_size);
+        ((left, top), (right - left, bottom - top))
+    }
+
+    pub fn load(path: &str) -> Result<Layout, String> {
+        let text = fs::read_to_string(path).map_err(|error| format!("couldn't read {path}: {error}"))?;
+        Layout::from_profile(&text).map_err(|error| format!("{path}: {error}"))
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        fs::write(path, self.to_profile()).map_err(|error| format!("couldn't write {path}: {error}"))
+    }
+
+    // one `name = value` per line, pairs are written as `x, y`
+    pub fn to_profile(self) -> String {
+        format!(
+            "tile_x = {}\ntile_y = {}\ntile_size = {}\ncolumn_clues = {}, {}\nrow_clues = {}, {}\n",
+            self.tile_x,
+            self.tile_y,
+            self.tile_size,
+            self.column_clues.0,
+            self.column_clues.1,
+            self.row_clues.0,
+            self.row_clues.1,
+        )
+    }
+
+    pub fn from_profile(text: &str) -> Result<Layout, String> {
+        let mut values = HashMap::new();
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+            let (name, value) = line.split_once('=').ok_or_else(|| format!("expected `name = value`, got `{line}`"))?;
+            let numbers = value.split(',')
+                .map(|number| number.trim().parse::<i32>().map_err(|_| format!("`{}` isn't a number", number.trim())))
+                .collect::<Result<Vec<_>, _>>()?;
+            values.insert(name.trim(), numbers);
+        }
+        let mut take = |name: &str, count: usize| match values.remove(name) {
+            Some(numbers) if numbers.len() == count => Ok(numbers),
+            Some(_) if count == 1 => Err(format!("{name} should be a single number")),
+            Some(_) => Err(format!("{name} should be {count} numbers separated by commas")),
+            None => Err(format!("{name} is missing")),
+        };
+        let layout = Layout {
+            tile_x: take("tile_x", 1)?[0],
+            tile_y: take("tile_y", 1)?[0],
+            tile_size: take("tile_size", 1)?[0],
+            column_clues: take("column_clues", 2).map(|pair| (pair[0], pair[1]))?,
+            row_clues: take("row_clues", 2).map(|pair| (pair[0], pair[1]))?,
+        };
+        if let Some(name) = values.keys().next() {
+            return Err(format!("there's no setting called {name}"));
+        }
+        if layout.tile_size <= CAPTURE_SIZE {
+            return Err(format!("tile_size has to be bigger than {CAPTURE_SIZE}"));
+        }
+        Ok(layout)
+    }
+}
+
+// finds the board in a screenshot of the whole screen
+//
+// every spot that looks enough like a ground or chest template is a tile, by the same measure
+// recognition uses, the most common gap between tiles is the tile size, and the ground template
+// that matched says which tile it is, so each match votes for where the top left tile has to be.
+// ground templates are in reading order, the chest doesn't get a vote since it can be anywhere.
+pub fn calibrate(screenshot: &RgbaImage, ground_images: &[RgbaImage], chest_image: &RgbaImage, threshold: f32) -> Result<Layout, String> {
+    let patterns = patterns(ground_images, chest_image, threshold);
+    if !patterns.iter().any(|pattern| pattern.tiles.iter().any(Option::is_some)) {
+        return Err(format!("every ground template looks like bare floor at --threshold {threshold}, it needs raising"));
+    }
+    let mut matches = scan(screenshot, &patterns, threshold);
+    if matches.is_empty() {
+        // only worth the time once it's failed, it goes a lot slower
+        let closest = scan(screenshot, &patterns, REPORT_BELOW).iter().map(|&(_, _, score, _)| score).fold(0.0, f32::max);
+        if closest == 0.0 {
+            return Err("couldn't find any tiles on the screen, is a puzzle showing?".to_string());
+        }
+        return Err(format!(
+            "couldn't find any tiles on the screen, the closest thing to one matched {:.0}% and --threshold is {threshold}",
+            closest * 100.0,
+        ));
+    }
+
+    // spots a pixel or two off a tile's crop match nearly as well, only the best one around counts
+    let size = CAPTURE_SIZE as usize;
+    let scores: HashMap<(usize, usize), f32> = matches.iter().map(|&(x, y, score, _)| ((x, y), score)).collect();
+    matches.retain(|&(x, y, score, _)| {
+        (y.saturating_sub(size - 1)..y + size).all(|ny| {
+            (x.saturating_sub(size - 1)..x + size).all(|nx| {
+                scores.get(&(nx, ny)).is_none_or(|&other| other < score || (other == score && (ny, nx) >= (y, x)))
+            })
+        })
+    });
+
+    let tile_size = spacing(matches.iter().map(|&(x, _, _, _)| x as i32))
+        .or_else(|| spacing(matches.iter().map(|&(_, y, _, _)| y as i32)))
+        .ok_or("found tiles, but not enough of them to tell how far apart they are")?;
+
+    let inset = (tile_size - CAPTURE_SIZE) / 2;
+    let votes = matches.iter().flat_map(|(x, y, _, tiles)| {
+        tiles.iter().flatten().map(move |i| {
+            let (column, row) = ((i % 8) as i32, (i / 8) as i32);
+            (*x as i32 - inset - column * tile_size, *y as i32 - inset - row * tile_size)
+        })
+    });
+    let ((tile_x, tile_y), count) = most_common(votes).ok_or("only found the chest, that's not enough to place the board")?;
+    // a couple of stray matches agreeing with each other isn't a board
+    if count < 8 {
+        return Err(format!("only {count} tiles agree on where the board is"));
+    }
+
+    let reference = Layout::REFERENCE;
+    let scale = |offset: i32| offset * tile_size / reference.tile_size;
+    Ok(Layout {
+        tile_x,
+        tile_y,
+        tile_size,
+        column_clues: (scale(reference.column_clues.0), scale(reference.column_clues.1)),
+        row_clues: (scale(reference.row_clues.0), scale(reference.row_clues.1)),
+    })
+}
+
+// a template to look for and every tile it belongs to, ground tiles that look exactly
+// the same share one and ones too plain to tell apart from the floor are left out
+struct Pattern {
+    // where in the crop and what colour, brightest first, most of the screen is dark
+    // so those rule a spot out the soonest
+    pixels: Vec<(usize, usize, [u8; 4])>,
+    tiles: Vec<Option<usize>>,
+}
+
+fn patterns(ground_images: &[RgbaImage], chest_image: &RgbaImage, threshold: f32) -> Vec<Pattern> {
+    let images = ground_images.iter().enumerate().map(|(i, image)| (Some(i), image)).chain([(None, chest_image)]);
+    let mut patterns: Vec<(&RgbaImage, Vec<Option<usize>>)> = vec![];
+    for (tile, image) in images {
+        match patterns.iter_mut().find(|(other, _)| *other == image) {
+            Some((_, tiles)) => tiles.push(tile),
+            None => patterns.push((image, vec![tile])),
+        }
+    }
+    patterns.into_iter().filter_map(|(image, tiles)| {
+        let mut pixels: Vec<(usize, usize, [u8; 4])> = image.enumerate_pixels().map(|(x, y, pixel)| (x as usize, y as usize, pixel.0)).collect();
+        // one that a patch of a single colour matches just as well would find bare floor everywhere,
+        // any colour between two of its own is close enough to all the ones that could do it
+        let colours: Vec<[u8; 4]> = pixels.iter().map(|pixel| pixel.2).collect();
+        let between = |a: [u8; 4], b: [u8; 4]| [0, 1, 2, 3].map(|i| ((a[i] as u16 + b[i] as u16) / 2) as u8);
+        let flat = colours.iter()
+            .flat_map(|&a| colours.iter().map(move |&b| between(a, b)))
+            .map(|colour| colours.iter().filter(|&&other| close(other, colour)).count())
+            .max()?;
+        if flat as f32 / colours.len() as f32 >= threshold {
+            return None;
+        }
+        pixels.sort_by_key(|&(_, _, [r, g, b, _])| Reverse(r as u32 + g as u32 + b as u32));
+        Some(Pattern { pixels, tiles })
+    }).collect()
+}
+
+// top left corner, score and tiles of every spot on the screen that matches a pattern at least
+// as well as the threshold, scored like recognition's similarity()
+fn scan(screenshot: &RgbaImage, patterns: &[Pattern], threshold: f32) -> Vec<(usize, usize, f32, Vec<Option<usize>>)> {
+    let size = CAPTURE_SIZE as usize;
+    let pixel_count = size * size;
+    // a spot stops getting compared once more pixels are off than could still make the threshold
+    let allowed = ((1.0 - threshold) * pixel_count as f32) as usize;
+    let (width, height) = (screenshot.width() as usize, screenshot.height() as usize);
+    let raw = screenshot.as_raw();
+    let pixel_at = |x: usize, y: usize| -> [u8; 4] { raw[(y * width + x) * 4..(y * width + x + 1) * 4].try_into().unwrap() };
+
+    let mut matches = vec![];
+    for y in 0..height.saturating_sub(size - 1) {
+        for x in 0..width.saturating_sub(size - 1) {
+            let mut best = 0.0;
+            let mut tiles = vec![];
+            for pattern in patterns {
+                let mut off = 0;
+                for &(px, py, colour) in pattern.pixels.iter() {
+                    if !close(pixel_at(x + px, y + py), colour) {
+                        off += 1;
+                        if off > allowed {
+                            break;
+                        }
+                    }
+                }
+                if off <= allowed {
+                    best = f32::max(best, (pixel_count - off) as f32 / pixel_count as f32);
+                    tiles.extend(pattern.tiles.iter().copied());
+                }
+            }
+            if !tiles.is_empty() {
+                matches.push((x, y, best, tiles));
+            }
+        }
+    }
+    matches
+}
+
+// most common gap between any two positions further apart than a crop, a template that also
+// matches a pixel or two off its tile only adds a few odd gaps, and gaps of two tiles or more
+// always come up less often than gaps of one
+fn spacing(positions: impl Iterator<Item = i32>) -> Option<i32> {
+    let mut positions: Vec<i32> = positions.collect();
+    positions.sort();
+    positions.dedup();
+    let gaps = positions.iter().enumerate().flat_map(|(i, a)| positions[i + 1..].iter().map(move |b| b - a));
+    most_common(gaps.filter(|&gap| gap > CAPTURE_SIZE)).map(|(gap, _)| gap)
+}
+
+// the value that shows up most and how many times it did
+fn most_common<T: Hash + Eq + Copy>(values: impl Iterator<Item = T>) -> Option<(T, usize)> {
+    let mut counts = HashMap::new();
+    for value in values {
+        *counts.entry(value).or_insert(0) += 1;
+    }
+    counts.into_iter().max_by_key(|&(_, count)| count)
+}
@@ -0,0 +1,781 @@
+use std::{cmp::Reverse, collections::HashSet, sync::{atomic::{AtomicBool, Ordering}, Mutex, OnceLock}, time::Instant};
+
+use contradiction::{Contradiction, Line};
+use options::SolveOptions;
+use progress::SolveEvent;
+use rules::{Change, Puzzle, RuleSet};
+use stats::{SolveStats, StatsCounters};
+use transposition::TranspositionTable;
+use tree::{NodeStatus, Structure};
+
+pub mod check;
+pub mod contradiction;
+pub mod explain;
+pub mod lint;
+pub mod options;
+mod parallel;
+pub mod progress;
+pub mod race;
+pub mod room;
+pub mod rules;
+pub mod session;
+pub mod stats;
+mod transposition;
+pub mod tree;
+mod zobrist;
+
+// stands in for a clue when working out which clues are to blame, any number of walls fits it
+const ANY_COUNT: usize = usize::MAX;
+
+#[derive(PartialEq, Eq, Clone, Hash)]
+pub enum Tile {
+    Unsure,
+    Chest,
+    Monster,
+    Ground,
+    Wall,
+}
+
+impl Tile {
+    // how the tile shows up in format_board
+    pub fn symbol(&self) -> char {
+        match self {
+            Tile::Chest => 'O',
+            Tile::Unsure => '?',
+            Tile::Monster => '!',
+            Tile::Ground => '_',
+            Tile::Wall => '#',
+        }
+    }
+
+    pub fn from_symbol(symbol: char) -> Option<Tile> {
+        match symbol {
+            'O' => Some(Tile::Chest),
+            '?' => Some(Tile::Unsure),
+            '!' => Some(Tile::Monster),
+            '_' => Some(Tile::Ground),
+            '#' => Some(Tile::Wall),
+            _ => None,
+        }
+    }
+}
+
+pub enum SolveResult {
+    Solved(Vec<Vec<Tile>>),
+    // every possibility has been tried
+    Unsolvable,
+    // ran out of budget or got cancelled, holds the most collapsed matrix reached
+    Exhausted(Vec<Vec<Tile>>),
+}
+
+// what solve() came up with, and what it took to get there
+pub struct SolveReport {
+    pub result: SolveResult,
+    pub stats: SolveStats,
+}
+
+pub fn solve(matrix: &[Vec<Tile>], nums_columns: &[usize], nums_rows: &[usize], options: &SolveOptions) -> SolveReport {
+    let search = Search::new(matrix, nums_columns, nums_rows, options);
+    // certainties first, so they get counted along with the rest of the work
+    let mut matrix = matrix.to_vec();
+    if search.settle(&mut matrix).is_err() {
+        return search.finish(None);
+    }
+    let root = search.record(None, Structure::Root, &matrix, NodeStatus::Open);
+    let solution = if first_unsure(&matrix).is_none() {
+        is_solved(&matrix, nums_columns, nums_rows).then(|| {
+            search.mark(root, NodeStatus::Solved);
+            matrix
+        })
+    } else if options.threads > 1 {
+        parallel::explore_parallel(&search, &matrix, root)
+    } else if search.visit(&matrix, 64 - count_unsure(&matrix), 0) {
+        // the parallel version visits the root while splitting it up, same count either way
+        explore(&search, &matrix, zobrist::hash(&matrix), 0, root)
+    } else {
+        None
+    };
+    search.finish(solution)
+}
+
+// everything shared by the branches of a single solve
+struct Search<'a> {
+    nums_columns: &'a [usize],
+    nums_rows: &'a [usize],
+    options: &'a SolveOptions,
+    // only explore() and its parallel version use it, made on first use so nothing else pays for 8MB
+    past_matrices: OnceLock<TranspositionTable>,
+    stats: StatsCounters,
+    start: Instant,
+    exhausted: AtomicBool,
+    best: Mutex<(usize, Vec<Vec<Tile>>)>,
+}
+
+impl<'a> Search<'a> {
+    fn new(matrix: &[Vec<Tile>], nums_columns: &'a [usize], nums_rows: &'a [usize], options: &'a SolveOptions) -> Self {
+        Search {
+            nums_columns,
+            nums_rows,
+            options,
+            past_matrices: OnceLock::new(),
+            stats: StatsCounters::default(),
+            start: Instant::now(),
+            exhausted: AtomicBool::new(false),
+            best: Mutex::new((64 - count_unsure(matrix), matrix.to_vec())),
+        }
+    }
+
+    // counts the matrix against the budget, false if it shouldn't be explored anymore
+    fn visit(&self, matrix: &[Vec<Tile>], certainty: usize, depth: usize) -> bool {
+        if self.stopped() {
+            return false;
+        }
+        let nodes = self.stats.nodes.fetch_add(1, Ordering::Relaxed) + 1;
+        let over_limit = self.options.node_limit.is_some_and(|limit| nodes > limit);
+        let past_deadline = self.options.deadline.is_some_and(|deadline| Instant::now() >= deadline);
+        if over_limit || past_deadline {
+            self.exhausted.store(true, Ordering::Relaxed);
+            return false;
+        }
+        self.stats.max_depth.fetch_max(depth, Ordering::Relaxed);
+        let mut best = self.best.lock().unwrap();
+        if certainty > best.0 {
+            *best = (certainty, matrix.to_vec());
+        }
+        true
+    }
+
+    fn past_matrices(&self) -> &TranspositionTable {
+        self.past_matrices.get_or_init(TranspositionTable::default)
+    }
+
+    fn stopped(&self) -> bool {
+        self.exhausted.load(Ordering::Relaxed) || self.options.cancel.is_cancelled()
+    }
+
+    // find_contradiction, keeping count of what broke
+    fn check(&self, matrix: &[Vec<Tile>]) -> Option<Contradiction> {
+        let contradiction = StatsCounters::time(&self.stats.checking_nanos, || {
+            find_contradiction(matrix, self.nums_columns, self.nums_rows)
+        });
+        if let Some(contradiction) = &contradiction {
+            StatsCounters::count(&self.stats.contradictions[contradiction.category()]);
+        }
+        contradiction
+    }
+
+    // a single round of certainty collapses, returns what changed
+    fn propagate(&self, matrix: &mut [Vec<Tile>]) -> Vec<Change> {
+        let changes = StatsCounters::time(&self.stats.propagation_nanos, || {
+            self.options.rules.apply(matrix, &Puzzle { nums_columns: self.nums_columns, nums_rows: self.nums_rows })
+        });
+        StatsCounters::count(&self.stats.propagation_rounds);
+        self.options.notify(&SolveEvent::Propagation {
+            matrix,
+            unsure_count: count_unsure(matrix),
+        });
+        changes
+    }
+
+    // same as settle(), counted and with whatever made it fail
+    fn settle(&self, matrix: &mut [Vec<Tile>]) -> Result<(), Contradiction> {
+        loop {
+            if let Some(contradiction) = self.check(matrix) {
+                return Err(contradiction);
+            }
+            let last_matrix = matrix.to_vec();
+            self.propagate(matrix);
+            if matrix == last_matrix.as_slice() {
+                return Ok(());
+            }
+        }
+    }
+
+    // adds a node to the search tree, if one is being recorded
+    fn record(&self, parent: Option<usize>, structure: Structure, matrix: &[Vec<Tile>], status: NodeStatus) -> Option<usize> {
+        self.options.tree.as_ref()?.add(parent, structure, matrix, status)
+    }
+
+    fn mark(&self, node: Option<usize>, status: NodeStatus) {
+        if let Some(tree) = &self.options.tree {
+            tree.set_status(node, status);
+        }
+    }
+
+    fn finish(self, solution: Option<Vec<Vec<Tile>>>) -> SolveReport {
+        let stats = self.stats.snapshot(self.start.elapsed());
+        let result = match solution {
+            Some(solution) => SolveResult::Solved(solution),
+            None if self.stopped() => SolveResult::Exhausted(self.best.into_inner().unwrap().1),
+            None => SolveResult::Unsolvable,
+        };
+        SolveReport { result, stats }
+    }
+}
+
+fn explore(search: &Search, matrix: &[Vec<Tile>], hash: u64, depth: usize, node: Option<usize>) -> Option<Vec<Vec<Tile>>> {
+    // seek win
+    for branch in expand(search, matrix, hash, depth, node).iter() {
+        if branch.certainty == 64 {
+            // same as the parallel workers, a full board passing the checks can still miss a proper treasure room
+            if is_solved(&branch.matrix, search.nums_columns, search.nums_rows) {
+                search.mark(branch.node, NodeStatus::Solved);
+                return Some(branch.matrix.clone());
+            }
+            search.past_matrices().store(branch.hash, 0, true);
+            continue;
+        }
+        if !search.visit(&branch.matrix, branch.certainty, branch.depth) {
+            return None;
+        }
+        search.options.notify(&SolveEvent::Branch {
+            depth: branch.depth,
+            matrix: &branch.matrix,
+            unsure_count: 64 - branch.certainty,
+        });
+        let solution = explore(search, &branch.matrix, branch.hash, branch.depth, branch.node);
+        if solution.is_some() {
+            return solution;
+        }
+        // gone through all of it, unless the search got cut short
+        if !search.stopped() {
+            search.past_matrices().store(branch.hash, 64 - branch.certainty, true);
+        }
+    }
+    if !search.stopped() {
+        search.options.notify(&SolveEvent::Backtrack { depth });
+    }
+    None
+}
+
+// a collapsed matrix waiting to be explored
+struct Branch {
+    matrix: Vec<Vec<Tile>>,
+    certainty: usize,
+    hash: u64,
+    // collapses made since the search started
+    depth: usize,
+    // where it sits in the recorded search tree
+    node: Option<usize>,
+}
+
+// possible next steps from the given matrix, most certain first
+fn expand(search: &Search, matrix: &[Vec<Tile>], hash: u64, depth: usize, node: Option<usize>) -> Vec<Branch> {
+    let past_matrices = search.past_matrices();
+    search.mark(node, NodeStatus::Expanded);
+
+    // generate random collapses and weed out impossible and previously checked ones
+    let collapses = StatsCounters::time(&search.stats.branching_nanos, || collapse_random(matrix, search.nums_columns, search.nums_rows));
+    let mut branches = vec![];
+    for (structure, mut collapse) in collapses {
+        let mut hash = zobrist::rehash(hash, matrix, &collapse, structure.cells());
+        // every matrix propagation went through, they all lead to wherever the last one does
+        let mut hashes = vec![];
+        let status = loop {
+            // seen before means it's been explored already, or is waiting to be further up
+            if let Some(impossible) = past_matrices.probe(hash) {
+                StatsCounters::count(&search.stats.duplicates);
+                if impossible {
+                    StatsCounters::count(&search.stats.known_impossible);
+                }
+                break NodeStatus::Duplicate;
+            }
+            past_matrices.store(hash, count_unsure(&collapse), false);
+            hashes.push(hash);
+            if let Some(contradiction) = search.check(&collapse) {
+                for &hash in &hashes {
+                    past_matrices.store(hash, count_unsure(&collapse), true);
+                }
+                break NodeStatus::Contradiction(contradiction);
+            }
+            let changes = search.propagate(&mut collapse);
+            if changes.is_empty() {
+                break NodeStatus::Open;
+            }
+            hash = zobrist::apply(hash, &changes);
+        };
+        let keep = matches!(status, NodeStatus::Open);
+        let child = search.record(node, structure, &collapse, status);
+
+        // score and matrices based on unsure tiles
+        if keep {
+            branches.push(Branch {
+                certainty: 64 - count_unsure(&collapse),
+                matrix: collapse,
+                hash,
+                depth: depth + 1,
+                node: child,
+            });
+        }
+    }
+    branches.sort_by_key(|branch| Reverse(branch.certainty));
+    branches
+}
+
+pub fn count_unsure(matrix: &[Vec<Tile>]) -> usize {
+    matrix.iter().flatten().filter(|tile| tile == &&Tile::Unsure).count()
+}
+
+// every solution up to the limit, searching single tiles the same way solve_cells() does
+pub fn solve_all(matrix: &[Vec<Tile>], nums_columns: &[usize], nums_rows: &[usize], options: &SolveOptions, limit: usize) -> Vec<Vec<Vec<Tile>>> {
+    let search = Search::new(matrix, nums_columns, nums_rows, options);
+    let mut solutions = vec![];
+    let mut matrix = matrix.to_vec();
+    if search.settle(&mut matrix).is_ok() {
+        collect_cells(&search, &matrix, 0, limit, &mut solutions);
+    }
+    solutions
+}
+
+fn collect_cells(search: &Search, matrix: &[Vec<Tile>], depth: usize, limit: usize, solutions: &mut Vec<Vec<Vec<Tile>>>) {
+    let Some((x, y)) = first_unsure(matrix) else {
+        if is_solved(matrix, search.nums_columns, search.nums_rows) {
+            solutions.push(matrix.to_vec());
+        }
+        return;
+    };
+    if !search.visit(matrix, 64 - count_unsure(matrix), depth) {
+        return;
+    }
+    for tile in [Tile::Wall, Tile::Ground] {
+        if solutions.len() >= limit {
+            return;
+        }
+        let mut new_matrix = matrix.to_vec();
+        new_matrix[y][x] = tile;
+        if search.settle(&mut new_matrix).is_ok() {
+            collect_cells(search, &new_matrix, depth + 1, limit, solutions);
+        }
+    }
+}
+
+// depth first search over single tiles, no structures involved
+pub fn solve_cells(matrix: &[Vec<Tile>], nums_columns: &[usize], nums_rows: &[usize], options: &SolveOptions) -> SolveReport {
+    let search = Search::new(matrix, nums_columns, nums_rows, options);
+    let mut matrix = matrix.to_vec();
+    if search.settle(&mut matrix).is_err() {
+        return search.finish(None);
+    }
+    let root = search.record(None, Structure::Root, &matrix, NodeStatus::Open);
+    let solution = explore_cells(&search, &matrix, 0, root);
+    search.finish(solution)
+}
+
+fn explore_cells(search: &Search, matrix: &[Vec<Tile>], depth: usize, node: Option<usize>) -> Option<Vec<Vec<Tile>>> {
+    let Some((x, y)) = first_unsure(matrix) else {
+        if !is_solved(matrix, search.nums_columns, search.nums_rows) {
+            return None;
+        }
+        search.mark(node, NodeStatus::Solved);
+        return Some(matrix.to_vec());
+    };
+    let unsure_count = count_unsure(matrix);
+    if !search.visit(matrix, 64 - unsure_count, depth) {
+        return None;
+    }
+    search.options.notify(&SolveEvent::Branch { depth, matrix, unsure_count });
+    search.mark(node, NodeStatus::Expanded);
+    for tile in [Tile::Wall, Tile::Ground] {
+        let mut new_matrix = matrix.to_vec();
+        new_matrix[y][x] = tile.clone();
+        let settled = search.settle(&mut new_matrix);
+        let structure = Structure::Cell { x, y, tile };
+        if let Err(contradiction) = settled {
+            search.record(node, structure, &new_matrix, NodeStatus::Contradiction(contradiction));
+            continue;
+        }
+        let child = search.record(node, structure, &new_matrix, NodeStatus::Open);
+        let solution = explore_cells(search, &new_matrix, depth + 1, child);
+        if solution.is_some() {
+            return solution;
+        }
+    }
+    if !search.stopped() {
+        search.options.notify(&SolveEvent::Backtrack { depth });
+    }
+    None
+}
+
+pub fn first_unsure(matrix: &[Vec<Tile>]) -> Option<(usize, usize)> {
+    for (y, row) in matrix.iter().enumerate() {
+        if let Some(x) = row.iter().position(|tile| tile == &Tile::Unsure) {
+            return Some((x, y));
+        }
+    }
+    None
+}
+
+// collapse certainties until nothing changes anymore
+pub fn resolve_certainties(matrix: &mut [Vec<Tile>], nums_columns: &[usize], nums_rows: &[usize]) {
+    loop {
+        let last_matrix = matrix.to_vec();
+        collapse_certainties(matrix, nums_columns, nums_rows);
+        if matrix == last_matrix.as_slice() {
+            break;
+        }
+    }
+}
+
+// same as resolve_certainties with the rules in options, but bails out as soon as the matrix becomes impossible
+pub fn settle(matrix: &mut [Vec<Tile>], nums_columns: &[usize], nums_rows: &[usize], options: &SolveOptions) -> bool {
+    loop {
+        if !is_possible(matrix, nums_columns, nums_rows) {
+            return false;
+        }
+        let last_matrix = matrix.to_vec();
+        options.rules.apply(matrix, &Puzzle { nums_columns, nums_rows });
+        options.notify(&SolveEvent::Propagation {
+            matrix,
+            unsure_count: count_unsure(matrix),
+        });
+        if matrix == last_matrix.as_slice() {
+            return true;
+        }
+    }
+}
+
+// a fully collapsed matrix that breaks none of the rules
+pub fn is_solved(matrix: &[Vec<Tile>], nums_columns: &[usize], nums_rows: &[usize]) -> bool {
+    if first_unsure(matrix).is_some() || !is_possible(matrix, nums_columns, nums_rows) {
+        return false;
+    }
+    for y in 0..8 {
+        for x in 0..8 {
+            if matrix[y][x] == Tile::Chest && !has_treasure_room(matrix, x, y) {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+// a 3x3 ground room around the chest with exactly one exit in its side walls
+fn has_treasure_room(matrix: &[Vec<Tile>], x: usize, y: usize) -> bool {
+    room::placements(matrix, x, y).into_iter().any(|room| {
+        room.inside().all(|(x, y)| matches!(matrix[y][x], Tile::Ground | Tile::Chest))
+            && room.possible_exits(matrix).len() == 1
+    })
+}
+
+fn collapse_random(matrix: &[Vec<Tile>], nums_columns: &[usize], nums_rows: &[usize]) -> Vec<(Structure, Vec<Vec<Tile>>)> {
+    let directions: [(i32, i32); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+    let mut collapses = vec![];
+
+    // collapse monsters
+    for x in 0..8 {
+        for y in 0..8 {
+            if matrix[y][x] == Tile::Monster {
+                for (dx, dy) in directions {
+                    let nx = (x as i32 + dx) as usize;
+                    let ny = (y as i32 + dy) as usize;
+                    if let Some(row) = matrix.get(ny) {
+                        if let Some(tile) = row.get(nx) {
+                            // this collapse runs on a certainty collapsed matrix
+                            // there will never be only a single adjacent unsure tile
+                            // there will never be an adjacent unsure and ground tile at the same time
+                            // this means that every unsure tile can be an exit for the monster
+                            if tile == &Tile::Unsure {
+                                let mut new_matrix = matrix.to_vec();
+                                for (ddx, ddy) in directions {
+                                    let nnx = (x as i32 + ddx) as usize;
+                                    let nny = (y as i32 + ddy) as usize;
+                                    if let Some(row) = new_matrix.get_mut(nny) {
+                                        if let Some(tile) = row.get_mut(nnx) {
+                                            *tile = Tile::Wall;
+                                        }
+                                    }
+                                }
+                                new_matrix[ny][nx] = Tile::Ground;
+                                collapses.push((Structure::MonsterExit { x, y, exit: (nx, ny) }, new_matrix));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // collapse treasure rooms, one branch for every placement and exit that's still viable
+    for x in 0..8 {
+        for y in 0..8 {
+            if matrix[y][x] == Tile::Chest {
+                for room in room::placements(matrix, x, y) {
+                    for exit in room::viable_exits(matrix, room, nums_columns, nums_rows) {
+                        // an exit that was open already wasn't picked
+                        let structure = Structure::Room {
+                            left: room.left,
+                            top: room.top,
+                            exit: (matrix[exit.1][exit.0] == Tile::Unsure).then_some(exit),
+                        };
+                        collapses.push((structure, room.build(matrix, exit)));
+                    }
+                }
+            }
+        }
+    }
+
+    // TODO:
+    //  this takes too long
+    //  only adding this only when collapses are empty doesn't guarantee
+    //  that the above collapses aren't going to be impossible after certainty collapse
+    // possible solution 1
+    //  add certainty collapse here and remove it from solve()
+    //  THEN check if collapses are empty
+    //  this skips the step of adding them to the past_matrices list though..
+    // possible solution 2
+    //  separate this into another function that gets called in solve() if structural collapses are empty
+    //  remove past_matrices because that will prevent some stuff that might be possible from going through... or not?
+    //
+    //  maybe I just need to add this as a separate function anyway without anything else.
+
+    // collapse random tiles
+    for x in 0..8 {
+        for y in 0..8 {
+            // this collapse runs on a certainty collapsed matrix
+            // it will always be possible to collapse unsure tiles to either state
+            if matrix[y][x] == Tile::Unsure {
+                for tile in [Tile::Ground, Tile::Wall] {
+                    let mut new_matrix = matrix.to_vec();
+                    new_matrix[y][x] = tile.clone();
+                    collapses.push((Structure::Cell { x, y, tile }, new_matrix));
+                }
+            }
+        }
+    }
+
+    collapses
+}
+
+pub fn is_possible(matrix: &[Vec<Tile>], nums_columns: &[usize], nums_rows: &[usize]) -> bool {
+    find_contradiction(matrix, nums_columns, nums_rows).is_none()
+}
+
+// everything here works on 8x8 boards with 8 clues a side, what's off if the board isn't one
+pub fn check_size(matrix: &[Vec<Tile>], nums_columns: &[usize], nums_rows: &[usize]) -> Result<(), String> {
+    if nums_columns.len() != 8 || nums_rows.len() != 8 {
+        return Err(format!("there are {} column clues and {} row clues instead of 8 each", nums_columns.len(), nums_rows.len()));
+    }
+    if matrix.len() != 8 {
+        return Err(format!("the board has {} rows instead of 8", matrix.len()));
+    }
+    if let Some(y) = matrix.iter().position(|row| row.len() != 8) {
+        return Err(format!("row {} has {} tiles instead of 8", y + 1, matrix[y].len()));
+    }
+    Ok(())
+}
+
+// the first rule the matrix breaks, if any
+pub fn find_contradiction(matrix: &[Vec<Tile>], nums_columns: &[usize], nums_rows: &[usize]) -> Option<Contradiction> {
+    // nothing below would make sense of it, and no 8x8 solution can come from it
+    if check_size(matrix, nums_columns, nums_rows).is_err() {
+        return Some(Contradiction::WrongSize);
+    }
+
+    // check for 2x2 spaces
+    for x in 0..7 {
+        for y in 0..7 {
+            if 
+                matrix[y][x] == Tile::Ground &&
+                matrix[y+1][x] == Tile::Ground &&
+                matrix[y][x+1] == Tile::Ground &&
+                matrix[y+1][x+1] == Tile::Ground
+            {
+                // a chest anywhere around it means this could be part of a treasure room
+                let chest_found = matrix[y.saturating_sub(1)..(y + 3).min(8)].iter()
+                    .any(|row| row[x.saturating_sub(1)..(x + 3).min(8)].contains(&Tile::Chest));
+                if !chest_found {
+                    return Some(Contradiction::OpenArea { x, y });
+                }
+            }
+        }
+    }
+
+    // check that there exists at least one possible way to have the treasure room,
+    // one that still has a side wall that could be its exit
+    for x in 0..8 {
+        for y in 0..8 {
+            if matrix[y][x] == Tile::Chest {
+                let room_possible = room::placements(matrix, x, y).into_iter()
+                    .any(|room| !room.possible_exits(matrix).is_empty());
+                if !room_possible {
+                    return Some(Contradiction::NoTreasureRoom { x, y });
+                }
+            }
+        }
+    }
+
+    // check for monster being in dead-ends (no more than 1 ground tile around them)
+    let directions: [(i32, i32); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+    for x in 0..8 {
+        for y in 0..8 {
+            if matrix[y][x] == Tile::Monster {
+                let mut ground_tile_possible = false;
+                let mut ground_tile = false;
+                for (dx, dy) in directions {
+                    let nx = (x as i32 + dx) as usize;
+                    let ny = (y as i32 + dy) as usize;
+                    if let Some(row) = matrix.get(ny) {
+                        if let Some(tile) = row.get(nx) {
+                            match tile {
+                                Tile::Unsure => {
+                                    ground_tile_possible = true;
+                                },
+                                Tile::Ground => {
+                                    ground_tile_possible = true;
+                                    if ground_tile {
+                                        return Some(Contradiction::MonsterNotInDeadEnd { x, y });
+                                    } else {
+                                        ground_tile = true;
+                                    }
+                                }
+                                _ => (),
+                            }
+                        }
+                    }
+                }
+                if !ground_tile_possible {
+                    return Some(Contradiction::MonsterWalledIn { x, y });
+                }
+            }
+        }
+    }
+
+    // check that dead-ends contain monsters
+    for x in 0..8 {
+        for y in 0..8 {
+            if matrix[y][x] == Tile::Ground {
+                let mut adj_grounds = 0;
+                let mut adj_monster = false;
+                for (dx, dy) in directions {
+                    let nx = (x as i32 + dx) as usize;
+                    let ny = (y as i32 + dy) as usize;
+                    if let Some(row) = matrix.get(ny) {
+                        if let Some(tile) = row.get(nx) {
+                            match tile {
+                                Tile::Ground | Tile::Unsure => adj_grounds += 1,
+                                Tile::Monster => adj_monster = true,
+                                _ => (),
+                            }
+                        }
+                    }
+                }
+                if adj_grounds == 1 && !adj_monster {
+                    return Some(Contradiction::DeadEndWithoutMonster { x, y });
+                }
+            }
+        }
+    }
+
+    // check wall numbers
+    for i in 0..8 {
+        let wall_count = matrix[i].iter().filter(|item| item == &&Tile::Wall).count();
+        let unsure_count = matrix[i].iter().filter(|item| item == &&Tile::Unsure).count();
+        if wall_count > nums_rows[i] {
+            return Some(Contradiction::TooManyWalls { line: Line::Row(i), needed: nums_rows[i], walls: wall_count });
+        }
+        // a clue matching any count can always be met
+        if nums_rows[i] != ANY_COUNT && wall_count + unsure_count < nums_rows[i] {
+            return Some(Contradiction::TooFewWalls { line: Line::Row(i), needed: nums_rows[i], walls: wall_count, unsure: unsure_count });
+        }
+        let wall_count = matrix.iter().filter(|row| row[i] == Tile::Wall).count();
+        let unsure_count = matrix.iter().filter(|row| row[i] == Tile::Unsure).count();
+        if wall_count > nums_columns[i] {
+            return Some(Contradiction::TooManyWalls { line: Line::Column(i), needed: nums_columns[i], walls: wall_count });
+        }
+        if nums_columns[i] != ANY_COUNT && wall_count + unsure_count < nums_columns[i] {
+            return Some(Contradiction::TooFewWalls { line: Line::Column(i), needed: nums_columns[i], walls: wall_count, unsure: unsure_count });
+        }
+    }
+
+    // check for ground tile continuity
+    let mut flooded_coords = HashSet::new();
+    for x in 0..8 {
+        if let Some(y) = matrix.iter().position(|row| row[x] == Tile::Ground) {
+            flooded_coords.insert((x, y));
+            break;
+        }
+    }
+    if !flooded_coords.is_empty() {
+        loop {
+            let mut new_flooded_coords = vec![];
+            for (x, y) in flooded_coords.iter() {
+                for (dx, dy) in directions {
+                    let nx = (*x as i32 + dx) as usize;
+                    let ny = (*y as i32 + dy) as usize;
+                    let tile = matrix.get(ny).and_then(|row| row.get(nx));
+                    if matches!(tile, Some(Tile::Ground | Tile::Unsure)) && !flooded_coords.contains(&(nx, ny)) {
+                        new_flooded_coords.push((nx, ny));
+                    }
+                }
+            }
+            if new_flooded_coords.is_empty() {
+                break;
+            } else {
+                for new_flooded_coord in new_flooded_coords {
+                    flooded_coords.insert(new_flooded_coord);
+                }
+            }
+        }
+    }
+    for x in 0..8 {
+        for (y, row) in matrix.iter().enumerate() {
+            if row[x] == Tile::Ground && !flooded_coords.contains(&(x, y)) {
+                return Some(Contradiction::Disconnected { x, y });
+            }
+        }
+    }
+
+    // TODO (or not)
+    // check for treasure room placement rules (1 exit, 3x3ness, ...)
+    None
+}
+
+// one round of every standard deduction rule
+pub fn collapse_certainties(matrix: &mut [Vec<Tile>], nums_columns: &[usize], nums_rows: &[usize]) {
+    RuleSet::standard().apply(matrix, &Puzzle { nums_columns, nums_rows });
+}
+
+// matrix, column clues and row clues
+pub type ParsedBoard = (Vec<Vec<Tile>>, Vec<usize>, Vec<usize>);
+
+// reads a board back in from what format_board() wrote out
+pub fn parse_board(text: &str) -> Result<ParsedBoard, String> {
+    let mut lines = text.lines().filter(|line| !line.trim().is_empty());
+    let clue = |c: char| c.to_digit(10).map(|digit| digit as usize).ok_or(format!("`{c}` isn't a clue"));
+
+    let header = lines.next().ok_or("the board is empty")?;
+    let nums_columns = header.trim().chars().map(clue).collect::<Result<Vec<_>, _>>()?;
+    let mut matrix = vec![];
+    let mut nums_rows = vec![];
+    for line in lines {
+        let mut chars = line.trim().chars();
+        nums_rows.push(clue(chars.next().unwrap())?);
+        let row = chars.map(|c| Tile::from_symbol(c).ok_or(format!("`{c}` isn't a tile"))).collect::<Result<Vec<_>, _>>()?;
+        if row.len() != nums_columns.len() {
+            return Err(format!("row {} has {} tiles instead of {}", matrix.len() + 1, row.len(), nums_columns.len()));
+        }
+        matrix.push(row);
+    }
+    if matrix.len() != 8 || nums_columns.len() != 8 {
+        return Err(format!("the board is {}x{} instead of 8x8", nums_columns.len(), matrix.len()));
+    }
+    Ok((matrix, nums_columns, nums_rows))
+}
+
+// the board as text, column clues along the top and row clues down the left
+pub fn format_board(matrix: &[Vec<Tile>], nums_columns: &[usize], nums_rows: &[usize]) -> String {
+    let mut text = " ".to_string();
+    for col_num in nums_columns {
+        text += &col_num.to_string();
+    }
+    text += "\n";
+    for (row, clue) in matrix.iter().zip(nums_rows) {
+        text += &clue.to_string();
+        for item in row {
+            text.push(item.symbol());
+        }
+        text += "\n";
+    }
+    text
+}
+
@@ -0,0 +1,108 @@
+use std::fmt;
+
+use crate::{check_size, contradiction::{Line, Position}, room, Tile, ANY_COUNT};
+
+// something wrong with the puzzle itself, cheap enough to find before any searching
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Problem {
+    // every wall is counted once by its row and once by its column
+    ClueSums { rows: usize, columns: usize },
+    // more walls than the line has cells that could hold one
+    ClueTooHigh { line: Line, needed: usize, room: usize },
+    NoRoomPlacement { x: usize, y: usize },
+    // nothing around the monster could ever be ground
+    MonsterBoxedIn { x: usize, y: usize },
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Problem::ClueSums { rows, columns } => write!(f, "the row clues add up to {rows} but the column clues add up to {columns}"),
+            Problem::ClueTooHigh { line, needed, room } => write!(f, "{line} needs {needed} walls but only has room for {room}"),
+            Problem::NoRoomPlacement { x, y } => write!(f, "the chest at {} has no room for a treasure room around it", Position(x, y)),
+            Problem::MonsterBoxedIn { x, y } => write!(f, "the monster at {} is boxed in, nothing next to it can be open", Position(x, y)),
+        }
+    }
+}
+
+// every problem with the puzzle that doesn't need a search to find, in reading order
+//
+// only the monsters, chests and clues count, walls and ground somebody put down are
+// left to the solver or the checker.
+pub fn lint(board: &[Vec<Tile>], nums_columns: &[usize], nums_rows: &[usize]) -> Result<Vec<Problem>, String> {
+    check_size(board, nums_columns, nums_rows)?;
+    let matrix: Vec<Vec<Tile>> = board.iter().map(|row| {
+        row.iter().map(|tile| match tile {
+            Tile::Wall | Tile::Ground => Tile::Unsure,
+            tile => tile.clone(),
+        }).collect()
+    }).collect();
+    let mut problems = vec![];
+
+    // clues matching any count can't be added up
+    if !nums_rows.contains(&ANY_COUNT) && !nums_columns.contains(&ANY_COUNT) {
+        let rows = nums_rows.iter().sum();
+        let columns = nums_columns.iter().sum();
+        if rows != columns {
+            problems.push(Problem::ClueSums { rows, columns });
+        }
+    }
+
+    // monsters and chests take up cells that walls can't
+    for i in 0..8 {
+        let room = matrix[i].iter().filter(|tile| tile == &&Tile::Unsure).count();
+        if nums_rows[i] != ANY_COUNT && nums_rows[i] > room {
+            problems.push(Problem::ClueTooHigh { line: Line::Row(i), needed: nums_rows[i], room });
+        }
+    }
+    for i in 0..8 {
+        let room = matrix.iter().filter(|row| row[i] == Tile::Unsure).count();
+        if nums_columns[i] != ANY_COUNT && nums_columns[i] > room {
+            problems.push(Problem::ClueTooHigh { line: Line::Column(i), needed: nums_columns[i], room });
+        }
+    }
+
+    let directions: [(i32, i32); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+    for y in 0..8 {
+        for x in 0..8 {
+            match matrix[y][x] {
+                Tile::Chest if room::placements(&matrix, x, y).is_empty() => {
+                    problems.push(Problem::NoRoomPlacement { x, y });
+                },
+                Tile::Monster => {
+                    // the edge of the board boxes it in as well
+                    let way_out = directions.iter().any(|&(dx, dy)| {
+                        let nx = (x as i32 + dx) as usize;
+                        let ny = (y as i32 + dy) as usize;
+                        matrix.get(ny).and_then(|row| row.get(nx)).is_some_and(|tile| tile == &Tile::Unsure)
+                    });
+                    if !way_out {
+                        problems.push(Problem::MonsterBoxedIn { x, y });
+                    }
+                },
+                _ => (),
+            }
+        }
+    }
+
+    Ok(problems)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{check, contradiction::Contradiction, find_contradiction, options::SolveOptions, solve, SolveResult, Tile};
+
+    // anything but 8x8 gets turned down rather than indexed past its end
+    #[test]
+    fn boards_that_arent_8x8() {
+        let small = vec![vec![Tile::Unsure; 7]; 7];
+        assert!(super::lint(&small, &[1; 7], &[1; 7]).is_err());
+        assert!(check::check(&small, &[1; 7], &[1; 7]).is_err());
+        assert_eq!(find_contradiction(&small, &[1; 7], &[1; 7]), Some(Contradiction::WrongSize));
+        assert!(matches!(solve(&small, &[1; 7], &[1; 7], &SolveOptions::default()).result, SolveResult::Unsolvable));
+
+        let short_row: Vec<Vec<Tile>> = (0..8).map(|y| vec![Tile::Unsure; if y == 3 { 7 } else { 8 }]).collect();
+        assert!(super::lint(&short_row, &[1; 8], &[1; 8]).is_err());
+        assert_eq!(find_contradiction(&short_row, &[1; 8], &[1; 8]), Some(Contradiction::WrongSize));
+    }
+}
@@ -1,851 +1,487 @@
-use std::{collections::HashSet, thread::sleep, time::Duration};
+use std::{process::exit, sync::Arc, thread::{available_parallelism, sleep}, time::{Duration, Instant}};
 
 use enigo::{Coordinate, Enigo, Mouse, Settings};
-use screenshots::{image::{imageops::overlay, io::Reader, DynamicImage, ImageBuffer, Rgba}, Screen};
+use last_call_bbs_dnd_solver::{check, contradiction::{Line, Position}, explain, format_board, lint, parse_board, options::SolveOptions, progress::ProgressLine, race, solve, tree::{self, SearchTree}, SolveResult, Tile};
+use layout::{Layout, CAPTURE_SIZE};
+use monitor::Snapshot;
 
-const TILE_X: i32 = 722;
-const TILE_Y: i32 = 428;
-const TILE_SIZE: i32 = 66;
+mod layout;
+mod monitor;
+mod overlay;
+mod recognition;
 
-const CAPTURE_SIZE: i32 = 8;
-
-const ORANGE: [u8; 4] = [250, 91, 69, 255];
-const GRAY: [u8; 4] = [98, 91, 77, 255];
-const BLACK: [u8; 4] = [0, 0, 0, 255];
-const WHITE: [u8; 4] = [255, 255, 255, 255];
-
-#[derive(PartialEq, Eq, Clone, Hash)]
-enum Tile {
-    Unsure,
-    Chest,
-    Monster,
-    Ground,
-    Wall,
-}
+use recognition::{ClueRead, Recognition, Templates};
 
 fn main() {
-    // load comparison image assets
-    let mut ground_images = vec![];
-    for i in 0..8 {
-        for j in 0..8 {
-            let tmp = Reader::open(format!("assets/ground/{j}x{i}.png")).unwrap().decode().unwrap();
-            ground_images.push(tmp);
-        }
+    let start = Instant::now();
+
+    // `check FILE` looks for mistakes in a board saved the way format_board() shows it
+    let mut args = std::env::args().skip(1);
+    if args.next().as_deref() == Some("check") {
+        let path = args.next().unwrap_or_else(|| {
+            eprintln!("usage: check FILE");
+            exit(2);
+        });
+        check_file(&path);
+        return;
     }
-    let chest_image = Reader::open(format!("assets/chest.png")).unwrap().decode().unwrap();
-
-    // detect dungeon layout from screen
-    let screens = Screen::all().unwrap();
-    let screen = screens[0];
 
-    let mut matrix = vec![];
-    for i in 0..8 {
-        let mut row = vec![];
-        for j in 0..8 {
-            let image = screen.capture_area(
-                TILE_X + (TILE_SIZE-CAPTURE_SIZE)/2 + (j*TILE_SIZE),
-                TILE_Y + (TILE_SIZE-CAPTURE_SIZE)/2 + (i*TILE_SIZE),
-                CAPTURE_SIZE as u32,
-                CAPTURE_SIZE as u32,
-            ).unwrap();
-            let image = DynamicImage::from(image);
-            if image == chest_image {
-                row.push(Tile::Chest);
-            } else if ground_images.contains(&image) {
-                row.push(Tile::Unsure);
-            } else {
-                row.push(Tile::Monster);
-            }
+    if std::env::args().any(|arg| arg == "--list-screens") {
+        let monitors = monitor::all().unwrap_or_else(|error| {
+            eprintln!("{error}");
+            exit(2);
+        });
+        for (i, monitor) in monitors.iter().enumerate() {
+            println!("{i}: {monitor}");
         }
-        matrix.push(row);
+        return;
     }
 
-    // detect numbers
-    let mut nums_columns = vec![];
-    let mut nums_rows = vec![];
-    for i in 0..8 {
-        // column numbers
-        let image = screen.capture_area(
-            TILE_X - TILE_SIZE,
-            TILE_Y + (i*TILE_SIZE),
-            TILE_SIZE as u32,
-            TILE_SIZE as u32,
-        ).unwrap();
-        nums_rows.push(detect_number(image));
-
-        // row numbers
-        let image = screen.capture_area(
-            TILE_X + 8 + (i*TILE_SIZE),
-            TILE_Y - 6 - TILE_SIZE,
-            TILE_SIZE as u32,
-            TILE_SIZE as u32,
-        ).unwrap();
-        nums_columns.push(detect_number(image));
+    // load comparison image assets, decoded once up front
+    let assets = arg_value("--assets");
+    let templates = match &assets {
+        Some(dir) => Templates::load(dir),
+        None => Templates::embedded(),
+    }.unwrap_or_else(|error| {
+        eprintln!("{error}");
+        exit(2);
+    });
+    if templates.monsters.is_empty() {
+        eprintln!("there are no monster templates, anything unlike ground or the chest is taken for a monster");
     }
-
-    // resolve certainties
-    loop {
-        let last_matrix = matrix.clone();
-        collapse_certainties(&mut matrix, &nums_columns, &nums_rows);
-        if matrix == last_matrix {
-            break;
-        }
+    // without them, walls and marks go the way of anything else no template matches
+    let unreadable_marks = templates.walls.is_empty() || templates.marks.is_empty();
+    if unreadable_marks && templates.monsters.is_empty() {
+        eprintln!("there are no wall or mark templates, any already on the board get taken for monsters, clear them in the game first");
+    } else if unreadable_marks {
+        eprintln!("there are no wall or mark templates, any already on the board come out unrecognized and stop the run");
     }
-
-    // solve the matrix by random collapses if necessary
-    let mut past_matrices = HashSet::new();
-    if matrix.iter().any(|row| row.iter().any(|tile| tile == &Tile::Unsure)) {
-        matrix = solve(&matrix, &nums_columns, &nums_rows, &mut past_matrices).unwrap();
+    if templates.monsters.is_empty() || templates.walls.is_empty() || templates.marks.is_empty() {
+        eprintln!("--dump-tiles DIR saves what every tile looks like, copy the missing templates from there and pass --assets");
     }
-    debug_print(&matrix, &nums_columns, &nums_rows);
 
-    // focus window
-    let mut enigo = Enigo::new(&Settings::default()).unwrap();
-    enigo.move_mouse(1920 + 5, 5, Coordinate::Abs).unwrap();
-    sleep(Duration::from_millis(50));
-    enigo.button(enigo::Button::Left, enigo::Direction::Click).unwrap();
-    sleep(Duration::from_millis(50));
-
-    // execute solution
-    for y in 0..8 {
-        for x in 0..8 {
-            if matrix[y][x] == Tile::Wall {
-                enigo.move_mouse(
-                    1920 + TILE_X + (x as i32 * TILE_SIZE) + (TILE_SIZE / 2),
-                    TILE_Y + (y as i32 * TILE_SIZE) + (TILE_SIZE / 2),
-                    Coordinate::Abs,
-                ).unwrap();
-                sleep(Duration::from_millis(50));
-                enigo.button(enigo::Button::Left, enigo::Direction::Click).unwrap();
-                sleep(Duration::from_millis(50));
-            }
-        }
+    // detect dungeon layout from screen
+    let monitor = monitor::pick(arg_value("--screen").as_deref()).unwrap_or_else(|error| {
+        eprintln!("{error}");
+        exit(2);
+    });
+
+    // how sure finding the board and reading tiles has to be
+    let threshold = arg_number("--threshold").unwrap_or(recognition::DEFAULT_THRESHOLD);
+    if !(0.0..=1.0).contains(&threshold) {
+        eprintln!("--threshold takes a number from 0 to 1, not {threshold}");
+        exit(2);
     }
-}
 
-fn solve(matrix: &Vec<Vec<Tile>>, nums_columns: &Vec<usize>, nums_rows: &Vec<usize>, past_matrices: &mut HashSet<Vec<Vec<Tile>>>) -> Option<Vec<Vec<Tile>>> {
-    // generate random collapses and weed out impossible and previously checked ones
-    let mut collapses = collapse_random(matrix);
-    'collapses_loop: for i in (0..collapses.len()).rev() {
-        loop {
-            if !past_matrices.insert(collapses[i].clone()) {
-                collapses.remove(i);
-                continue 'collapses_loop;
-            }
-            if !is_possible(&collapses[i], nums_columns, nums_rows) {
-                collapses.remove(i);
-                continue 'collapses_loop;
+    // find the board, once per setup, later runs reuse the saved layout
+    let layout_path = arg_value("--layout").unwrap_or_else(|| layout::PROFILE.to_string());
+    let calibrate = std::env::args().any(|arg| arg == "--calibrate") || !std::path::Path::new(&layout_path).exists();
+    let layout = if calibrate {
+        let screenshot = monitor.capture().unwrap_or_else(|error| {
+            eprintln!("{error}");
+            exit(2);
+        });
+        let layout = layout::calibrate(&screenshot, &templates.ground, &templates.chest, threshold).unwrap_or_else(|error| {
+            eprintln!("calibration failed: {error}");
+            exit(2);
+        });
+        layout.save(&layout_path).unwrap_or_else(|error| eprintln!("{error}, calibrating again next time"));
+        println!("found the board at {}, {} with {} pixel tiles", layout.tile_x, layout.tile_y, layout.tile_size);
+        layout
+    } else {
+        Layout::load(&layout_path).unwrap_or_else(|error| {
+            eprintln!("{error}");
+            eprintln!("fix it, or delete it or pass --calibrate to find the board again");
+            exit(2);
+        })
+    };
+
+    // everything gets read from a single capture, so the board can't change halfway through
+    let (origin, size) = layout.bounds();
+    let snapshot = monitor.snapshot(origin, size).unwrap_or_else(|error| {
+        eprintln!("{error}");
+        exit(2);
+    });
+
+    let dump_dir = arg_value("--dump-tiles");
+    if let Some(dir) = &dump_dir {
+        std::fs::create_dir_all(dir).unwrap_or_else(|error| {
+            eprintln!("couldn't make {dir}: {error}");
+            exit(2);
+        });
+    }
+    let mut matrix = vec![];
+    let mut reads = vec![];
+    let mut unrecognized = vec![];
+    for i in 0..8 {
+        let mut row = vec![];
+        let mut row_reads = vec![];
+        for j in 0..8 {
+            let image = snapshot.crop(layout.tile_crop(j, i), (CAPTURE_SIZE, CAPTURE_SIZE)).unwrap_or_else(|error| {
+                eprintln!("the tile in {} {error}", Position(j, i));
+                exit(2);
+            });
+            if let Some(dir) = &dump_dir {
+                let path = format!("{dir}/{j}x{i}.png");
+                image.save(&path).unwrap_or_else(|error| {
+                    eprintln!("couldn't save {path}: {error}");
+                    exit(2);
+                });
             }
-            let last_matrix = collapses[i].clone();
-            collapse_certainties(&mut collapses[i], nums_columns, nums_rows);
-            if last_matrix == collapses[i] {
-                break;
+            let recognition = recognition::classify(&image, &templates, threshold);
+            match recognition {
+                Recognition::Recognized(read) => row.push(read.kind.tile()),
+                Recognition::Unrecognized(read) => {
+                    unrecognized.push((j, i, read));
+                    row.push(Tile::Unsure);
+                },
             }
+            row_reads.push(recognition);
         }
+        matrix.push(row);
+        reads.push(row_reads);
     }
-
-    if collapses.is_empty() {
-        return None;
+    // saved before a bad read can stop the run, that's when it's needed
+    if let Some(path) = arg_value("--debug-recognition") {
+        overlay::draw(&snapshot, &layout, &reads, &templates).save(&path).unwrap_or_else(|error| {
+            eprintln!("couldn't save {path}: {error}");
+        });
     }
-
-    // score and matrices based on unsure tiles
-    let mut collapses: Vec<(Vec<Vec<Tile>>, usize)> = collapses.into_iter().map(|collapse| {
-        let mut certainty = 64;
-        for x in 0..8 {
-            for y in 0..8 {
-                if collapse[y][x] == Tile::Unsure {
-                    certainty -= 1;
-                }
-            }
-        }
-        (collapse, certainty)
-    }).collect();
-    collapses.sort_by(|(_, a), (_, b)| b.cmp(&a));
-
-    // seek win
-    for (collapse, certainty) in collapses.iter() {
-        if certainty == &64 {
-            return Some(collapse.clone());
+    // better to stop than to solve a puzzle that isn't the one on the screen
+    if !unrecognized.is_empty() {
+        eprintln!("couldn't make out every tile, is the board covered up? --threshold {threshold} can be lowered if it isn't");
+        for (x, y, read) in unrecognized {
+            eprintln!("  {} looks most like {}, but only {:.0}% sure", Position(x, y), read.kind, read.confidence * 100.0);
         }
-        let solution = solve(collapse, nums_columns, nums_rows, past_matrices);
-        if solution.is_some() {
-            return solution;
+        if unreadable_marks && !templates.monsters.is_empty() {
+            eprintln!("there are no wall or mark templates, so any put down already are among these, clear them in the game or add some");
         }
+        exit(1);
     }
-    None
-}
-
-fn collapse_random(matrix: &Vec<Vec<Tile>>) -> Vec<Vec<Vec<Tile>>> {
-    let directions: [(i32, i32); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
-    let mut collapses = vec![];
 
-    // collapse monsters
-    for x in 0..8 {
-        for y in 0..8 {
-            if matrix[y][x] == Tile::Monster {
-                for (dx, dy) in directions {
-                    let nx = (x as i32 + dx) as usize;
-                    let ny = (y as i32 + dy) as usize;
-                    if let Some(row) = matrix.get(ny) {
-                        if let Some(tile) = row.get(nx) {
-                            // this collapse runs on a certainty collapsed matrix
-                            // there will never be only a single adjacent unsure tile
-                            // there will never be an adjacent unsure and ground tile at the same time
-                            // this means that every unsure tile can be an exit for the monster
-                            if tile == &Tile::Unsure {
-                                let mut new_matrix = matrix.clone();
-                                for (ddx, ddy) in directions {
-                                    let nnx = (x as i32 + ddx) as usize;
-                                    let nny = (y as i32 + ddy) as usize;
-                                    if let Some(row) = new_matrix.get_mut(nny) {
-                                        if let Some(tile) = row.get_mut(nnx) {
-                                            *tile = Tile::Wall;
-                                        }
-                                    }
-                                }
-                                new_matrix[ny][nx] = Tile::Ground;
-                                collapses.push(new_matrix);
-                            }
-                        }
-                    }
-                }
+    // walls and marks the player already put down either stay and get solved around,
+    // or get cleared and the puzzle solved from scratch
+    let on_board = matrix.clone();
+    let clear_marks = std::env::args().any(|arg| arg == "--clear-marks");
+    let placed = on_board.iter().flatten().filter(|tile| matches!(tile, Tile::Wall | Tile::Ground)).count();
+    if placed > 0 && clear_marks {
+        println!("clearing the {placed} walls and marks already on the board");
+        for tile in matrix.iter_mut().flatten() {
+            if matches!(tile, Tile::Wall | Tile::Ground) {
+                *tile = Tile::Unsure;
             }
         }
+    } else if placed > 0 {
+        println!("keeping the {placed} walls and marks already on the board");
     }
 
-    // collapse treasure rooms
-    for x in 0..8 {
-        for y in 0..8 {
-            if matrix[y][x] == Tile::Chest {
-                for chest_offset_x in 0..3 {
-                    'seek_top_left: for chest_offset_y in 0..3 {
-                        let top_left_x = x - 1 - chest_offset_x;
-                        let top_left_y = y - 1 - chest_offset_y;
-                        // verify room position plausible
-                        let mut side_wall_ground = false;
-                        for room_x in 0..5 {
-                            for room_y in 0..5 {
-                                if let Some(row) = matrix.get(top_left_y + room_y) {
-                                    if let Some(tile) = row.get(top_left_x + room_x) {
-                                        if room_x == 0 || room_x == 4 || room_y == 0 || room_y == 4 {
-                                            // room wall
-                                            if !((room_x == 0 || room_x == 4) && (room_y == 0 || room_y == 4)) {
-                                                // not a corner (side wall)
-                                                match tile {
-                                                    Tile::Chest | Tile::Monster => continue 'seek_top_left,
-                                                    Tile::Ground => {
-                                                        if side_wall_ground {
-                                                            continue 'seek_top_left;
-                                                        } else {
-                                                            side_wall_ground = true;
-                                                        }
-                                                    }
-                                                    _ => (),
-                                                }
-                                            }
-                                        } else {
-                                            // room inside
-                                            match tile {
-                                                Tile::Monster | Tile::Wall => continue 'seek_top_left,
-                                                _ => (),
-                                            }
-                                        }
-                                    } else {
-                                        if !(room_x == 0 || room_x == 4 || room_y == 0 || room_y == 4) {
-                                            continue 'seek_top_left;
-                                        }
-                                    }
-                                } else {
-                                    if !(room_x == 0 || room_x == 4 || room_y == 0 || room_y == 4) {
-                                        continue 'seek_top_left;
-                                    }
-                                }
-                            }
-                        }
-                        // top left corner coord verified to be plausible
-                        // generate all certain structures with all possible exits
-                        let mut tmp_matrix = matrix.clone();
-                        for room_x in 0..5 {
-                            for room_y in 0..5 {
-                                if let Some(row) = tmp_matrix.get_mut(top_left_y + room_y) {
-                                    if let Some(tile) = row.get_mut(top_left_x + room_x) {
-                                        if room_x == 0 || room_x == 4 || room_y == 0 || room_y == 4 {
-                                            // room wall
-                                            if !((room_x == 0 || room_x == 4) && (room_y == 0 || room_y == 4)) {
-                                                // not a corner (side wall)
-                                                if tile == &Tile::Unsure {
-                                                    *tile = Tile::Wall;
-                                                }
-                                            }
-                                        } else {
-                                            // room inside
-                                            if tile == &Tile::Unsure {
-                                                *tile = Tile::Ground;
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                        // tmp_matrix contains walled off room or 1 exit room
-                        if side_wall_ground {
-                            // exit already predetermined
-                            collapses.push(tmp_matrix);
-                        } else {
-                            // any exit position plausible
-                            for room_x in 0..5 {
-                                for room_y in 0..5 {
-                                    if let Some(row) = tmp_matrix.get(top_left_y + room_y) {
-                                        if let Some(_) = row.get(top_left_x + room_x) {
-                                            if room_x == 0 || room_x == 4 || room_y == 0 || room_y == 4 {
-                                                if !((room_x == 0 || room_x == 4) && (room_y == 0 || room_y == 4)) {
-                                                    // room wall, not a corner (side wall)
-                                                    let mut exit_matrix = tmp_matrix.clone();
-                                                    exit_matrix[top_left_y + room_y][top_left_x + room_x] = Tile::Ground;
-                                                    collapses.push(exit_matrix);
-                                                }
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
+    // detect numbers
+    let (clues_rows, clues_columns) = read_clues(&snapshot, &layout, &templates).unwrap_or_else(|unreadable| {
+        eprintln!("couldn't read every clue:");
+        for (line, error) in unreadable {
+            eprintln!("  the clue for {line} {error}");
+        }
+        exit(1);
+    });
+    let nums_rows: Vec<usize> = clues_rows.iter().map(|clue| clue.number).collect();
+    let nums_columns: Vec<usize> = clues_columns.iter().map(|clue| clue.number).collect();
+
+    let mut options = SolveOptions {
+        node_limit: arg_number("--node-limit"),
+        deadline: arg_number("--timeout").map(|seconds: f64| {
+            let timeout = Duration::try_from_secs_f64(seconds).unwrap_or_else(|_| {
+                eprintln!("--timeout takes a number of seconds that isn't negative, not {seconds}");
+                exit(2);
+            });
+            Instant::now() + timeout
+        }),
+        threads: arg_number("--threads").unwrap_or_else(|| available_parallelism().map_or(1, |threads| threads.get())),
+        ..Default::default()
+    };
+    let progress = std::env::args().any(|arg| arg == "--progress").then(|| Arc::new(ProgressLine::default()));
+    if let Some(progress) = &progress {
+        options.observer = Some(progress.clone());
     }
-
-    // TODO:
-    //  this takes too long
-    //  only adding this only when collapses are empty doesn't guarantee
-    //  that the above collapses aren't going to be impossible after certainty collapse
-    // possible solution 1
-    //  add certainty collapse here and remove it from solve()
-    //  THEN check if collapses are empty
-    //  this skips the step of adding them to the past_matrices list though..
-    // possible solution 2
-    //  separate this into another function that gets called in solve() if structural collapses are empty
-    //  remove past_matrices because that will prevent some stuff that might be possible from going through... or not?
-    //
-    //  maybe I just need to add this as a separate function anyway without anything else.
-
-    // collapse random tiles
-    for x in 0..8 {
-        for y in 0..8 {
-            // this collapse runs on a certainty collapsed matrix
-            // it will always be possible to collapse unsure tiles to either state
-            if matrix[y][x] == Tile::Unsure {
-                let mut new_matrix = matrix.clone();
-                new_matrix[y][x] = Tile::Ground;
-                collapses.push(new_matrix);
-                let mut new_matrix = matrix.clone();
-                new_matrix[y][x] = Tile::Wall;
-                collapses.push(new_matrix);
-            }
+    let disabled_rules = arg_values("--disable-rule");
+    for name in disabled_rules.iter() {
+        if options.rules.remove(name).is_none() {
+            let names: Vec<_> = options.rules.names().collect();
+            eprintln!("there's no rule called {name}, pick from {}", names.join(", "));
+            exit(2);
         }
     }
-
-    collapses
-}
-
-fn is_possible(matrix: &Vec<Vec<Tile>>, nums_columns: &Vec<usize>, nums_rows: &Vec<usize>) -> bool {
-    // check for 2x2 spaces
-    for x in 0..7 {
-        for y in 0..7 {
-            if 
-                matrix[y][x] == Tile::Ground &&
-                matrix[y+1][x] == Tile::Ground &&
-                matrix[y][x+1] == Tile::Ground &&
-                matrix[y+1][x+1] == Tile::Ground
-            {
-                let mut chest_found = false;
-                for cx in 0..4 {
-                    for cy in 0..4 {
-                        if cx == 0 || cy == 0 || cx == 3 || cy == 3 {
-                            if let Some(row) = matrix.get(y - 1 + cy) {
-                                if let Some(tile) = row.get(x - 1 + cx) {
-                                    if tile == &Tile::Chest {
-                                        chest_found = true;
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-                if !chest_found {
-                    return false;
-                }
-            }
-        }
+    let dot_path = arg_value("--dot");
+    if dot_path.is_some() {
+        let node_limit = arg_number("--dot-nodes").unwrap_or(tree::DEFAULT_NODE_LIMIT);
+        options.tree = Some(Arc::new(SearchTree::new(node_limit)));
     }
 
-    // check that there exists at least one possible way to have the treasure room
-    for x in 0..8 {
-        for y in 0..8 {
-            if matrix[y][x] == Tile::Chest {
-                let mut ground_possible_coords = vec![];
-                // check for monsters or walls within the area of all possible treasure rooms
-                for dx in 0..3 {
-                    'chest_loop: for dy in 0..3 {
-                        for ix in 0..3 {
-                            for iy in 0..3 {
-                                if let Some(row) = matrix.get(y - dy + iy) {
-                                    if let Some(tile) = row.get(x - dx + ix) {
-                                        if tile == &Tile::Monster || tile == &Tile::Wall {
-                                            continue 'chest_loop;
-                                        }
-                                    } else {
-                                        continue 'chest_loop;
-                                    }
-                                } else {
-                                    continue 'chest_loop;
-                                }
-                            }
-                        }
-                        ground_possible_coords.push((dx, dy));
-                    }
-                }
-                // check for monsters and at least one non-wall tile along the edges
-                'wall_loop: for i in (0..ground_possible_coords.len()).rev() {
-                    let mut entrance_possible = false;
-                    let (dx, dy) = ground_possible_coords[i];
-                    for ix in 0..5 {
-                        for iy in 0..5 {
-                            if ix == 0 || iy == 0 || ix == 4 || iy == 4 {
-                                if let Some(row) = matrix.get(y - dy - 1 + iy) {
-                                    if let Some(tile) = row.get(x - dx - 1 + ix) {
-                                        match tile {
-                                            Tile::Monster | Tile::Chest => {
-                                                if !((ix == 0 || ix == 4) && (iy == 0 || iy == 4)) {
-                                                    ground_possible_coords.remove(i);
-                                                    continue 'wall_loop;
-                                                }
-                                            },
-                                            Tile::Ground | Tile::Unsure => {
-                                                entrance_possible = true;
-                                            },
-                                            _ => (),
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    if !entrance_possible {
-                        ground_possible_coords.remove(i);
-                    }
-                }
-                if ground_possible_coords.is_empty() {
-                    return false;
-                }
-            }
+    let show_stats = std::env::args().any(|arg| arg == "--stats");
+    if show_stats {
+        println!("recognition took {:.3?}", start.elapsed());
+        let lines = (0..8).map(Line::Row).zip(clues_rows.iter()).chain((0..8).map(Line::Column).zip(clues_columns.iter()));
+        if let Some((line, clue)) = lines.min_by(|a, b| a.1.margin.total_cmp(&b.1.margin)) {
+            println!(
+                "least clear clue: {} for {line}, {:.0}% match, {:.0}% ahead of the next number",
+                clue.number,
+                clue.score * 100.0,
+                clue.margin * 100.0,
+            );
         }
     }
 
-    // check for monster being in dead-ends (no more than 1 ground tile around them)
-    let directions: [(i32, i32); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
-    for x in 0..8 {
-        for y in 0..8 {
-            if matrix[y][x] == Tile::Monster {
-                let mut ground_tile_possible = false;
-                let mut ground_tile = false;
-                for (dx, dy) in directions {
-                    let nx = (x as i32 + dx) as usize;
-                    let ny = (y as i32 + dy) as usize;
-                    if let Some(row) = matrix.get(ny) {
-                        if let Some(tile) = row.get(nx) {
-                            match tile {
-                                Tile::Unsure => {
-                                    ground_tile_possible = true;
-                                },
-                                Tile::Ground => {
-                                    ground_tile_possible = true;
-                                    if ground_tile {
-                                        return false;
-                                    } else {
-                                        ground_tile = true;
-                                    }
-                                }
-                                _ => (),
-                            }
-                        }
-                    }
-                }
-                if !ground_tile_possible {
-                    return false;
-                }
-            }
-        }
+    // a misread or broken puzzle shows up here without having to search for it
+    let problems = lint::lint(&matrix, &nums_columns, &nums_rows).unwrap_or_else(|error| {
+        eprintln!("{error}");
+        exit(2);
+    });
+    if !problems.is_empty() {
+        eprintln!("the puzzle can't be solved:");
+        for problem in problems.iter() {
+            eprintln!("  {problem}");
+        }
+        print!("{}", format_board(&matrix, &nums_columns, &nums_rows));
+        exit(1);
     }
 
-    // check that dead-ends contain monsters
-    for x in 0..8 {
-        for y in 0..8 {
-            if matrix[y][x] == Tile::Ground {
-                let mut adj_grounds = 0;
-                let mut adj_monster = false;
-                for (dx, dy) in directions {
-                    let nx = (x as i32 + dx) as usize;
-                    let ny = (y as i32 + dy) as usize;
-                    if let Some(row) = matrix.get(ny) {
-                        if let Some(tile) = row.get(nx) {
-                            match tile {
-                                Tile::Ground | Tile::Unsure => adj_grounds += 1,
-                                Tile::Monster => adj_monster = true,
-                                _ => (),
-                            }
-                        }
-                    }
-                }
-                if adj_grounds == 1 && !adj_monster {
-                    return false;
-                }
+    let recognised_matrix = matrix.clone();
+    let result = if std::env::args().any(|arg| arg == "--race") {
+        // let every strategy have a go and take whichever finishes first
+        let result = race::race(&matrix, &nums_columns, &nums_rows, &race::Strategy::ALL, &options);
+        for attempt in result.attempts.iter() {
+            println!(
+                "{:<12} {:>10.3?} {}",
+                attempt.strategy.name(),
+                attempt.elapsed,
+                match attempt.result {
+                    SolveResult::Solved(_) => "solved",
+                    SolveResult::Unsolvable => "unsolvable",
+                    SolveResult::Exhausted(_) => "gave up",
+                },
+            );
+        }
+        if show_stats {
+            for attempt in result.attempts.iter() {
+                println!("{}:\n{}", attempt.strategy.name(), attempt.stats);
             }
         }
-    }
-
-    // check wall numbers
-    for i in 0..8 {
-        let wall_count = matrix[i].iter().filter(|item| item == &&Tile::Wall).count();
-        let unsure_count = matrix[i].iter().filter(|item| item == &&Tile::Unsure).count();
-        if wall_count > nums_rows[i] {
-            return false;
-        }
-        if wall_count + unsure_count < nums_rows[i] {
-            return false;
-        }
-        let wall_count = matrix.iter().filter(|row| row[i] == Tile::Wall).count();
-        let unsure_count = matrix.iter().filter(|row| row[i] == Tile::Unsure).count();
-        if wall_count > nums_columns[i] {
-            return false;
-        }
-        if wall_count + unsure_count < nums_columns[i] {
-            return false;
-        }
-    }
-
-    // check for ground tile continuity
-    let mut flooded_coords = HashSet::new();
-    'seek_ground: for x in 0..8 {
-        for y in 0..8 {
-            if matrix[y][x] == Tile::Ground {
-                flooded_coords.insert((x, y));
-                break 'seek_ground;
+        if !disabled_rules.is_empty() {
+            for attempt in result.attempts.iter() {
+                println!("{}: {} branch points without {}", attempt.strategy.name(), attempt.stats.nodes, disabled_rules.join(", "));
             }
         }
-    }
-    if !flooded_coords.is_empty() {
-        loop {
-            let mut new_flooded_coords = vec![];
-            for (x, y) in flooded_coords.iter() {
-                for (dx, dy) in directions {
-                    let nx = (*x as i32 + dx) as usize;
-                    let ny = (*y as i32 + dy) as usize;
-                    if let Some(row) = matrix.get(ny) {
-                        if let Some(tile) = row.get(nx) {
-                            match tile {
-                                Tile::Ground | Tile::Unsure => {
-                                    if !flooded_coords.contains(&(nx, ny)) {
-                                        new_flooded_coords.push((nx, ny));
-                                    }
-                                },
-                                _ => (),
-                            }
-                        }
-                    }
+        match result.winner {
+            Some((strategy, solution)) => {
+                println!("winner: {}", strategy.name());
+                SolveResult::Solved(solution)
+            },
+            // a strategy only says unsolvable once it's tried everything, which beats any that gave up
+            None => {
+                let results: Vec<SolveResult> = result.attempts.into_iter().map(|attempt| attempt.result).collect();
+                if results.iter().any(|result| matches!(result, SolveResult::Unsolvable)) {
+                    SolveResult::Unsolvable
+                } else {
+                    results.into_iter().find(|result| matches!(result, SolveResult::Exhausted(_))).unwrap_or(SolveResult::Unsolvable)
                 }
-            }
-            if new_flooded_coords.is_empty() {
-                break;
-            } else {
-                for new_flooded_coord in new_flooded_coords {
-                    flooded_coords.insert(new_flooded_coord);
-                }
-            }
-        }
+            },
+        }
+    } else {
+        // resolve certainties, then random collapses if that's not enough
+        let report = solve(&matrix, &nums_columns, &nums_rows, &options);
+        if show_stats {
+            print!("{}", report.stats);
+        }
+        // 0 when the certainty collapses got there on their own
+        if !disabled_rules.is_empty() {
+            println!("{} branch points without {}", report.stats.nodes, disabled_rules.join(", "));
+        }
+        report.result
+    };
+    if let Some(progress) = &progress {
+        progress.finish();
     }
-    for x in 0..8 {
-        for y in 0..8 {
-            if matrix[y][x] == Tile::Ground && !flooded_coords.contains(&(x, y)){
-                return false;
-            }
-        }
+    if let (Some(path), Some(tree)) = (&dot_path, &options.tree) {
+        tree.write_dot(path).unwrap_or_else(|error| {
+            eprintln!("couldn't save {path}: {error}");
+        });
     }
-
-    // TODO (or not)
-    // check for treasure room placement rules (1 exit, 3x3ness, ...)
-    true
-}
-
-fn collapse_certainties(matrix: &mut Vec<Vec<Tile>>, nums_columns: &Vec<usize>, nums_rows: &Vec<usize>) {
-    let directions: [(i32, i32); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
-
-    // collapse resolved rows
-    for i in 0..8 {
-        let unsure_count = matrix[i].iter().filter(|item| item == &&Tile::Unsure).count();
-        let wall_count = matrix[i].iter().filter(|item| item == &&Tile::Wall).count();
-        if unsure_count == nums_rows[i] - wall_count {
-            for j in 0..8 {
-                if matrix[i][j] == Tile::Unsure {
-                    matrix[i][j] = Tile::Wall;
-                }
+    let matrix = match result {
+        SolveResult::Solved(solution) => solution,
+        SolveResult::Unsolvable => {
+            eprintln!("no solution");
+            eprint!("{}", explain::explain(&recognised_matrix, &nums_columns, &nums_rows, &options));
+            if placed > 0 && !clear_marks {
+                eprintln!("the walls and marks already on the board might be wrong, --clear-marks solves without them");
             }
-        }
-        if wall_count == nums_rows[i] && unsure_count > 0 {
-            for j in 0..8 {
-                if matrix[i][j] == Tile::Unsure {
-                    matrix[i][j] = Tile::Ground;
-                }
+            if unreadable_marks && templates.monsters.is_empty() {
+                eprintln!("walls or marks already on the board would have been taken for monsters, clear them in the game and try again");
             }
-        }
-    }
+            exit(1);
+        },
+        SolveResult::Exhausted(best) => {
+            // don't click anything, show how far it got instead
+            eprintln!("gave up before finding a solution, got this far:");
+            print!("{}", format_board(&best, &nums_columns, &nums_rows));
+            exit(1);
+        },
+    };
+    print!("{}", format_board(&matrix, &nums_columns, &nums_rows));
 
-    // collapse resolved columns
-    for i in 0..8 {
-        let unsure_count = matrix.iter().filter(|row| row[i] == Tile::Unsure).count();
-        let wall_count = matrix.iter().filter(|row| row[i] == Tile::Wall).count();
-        if unsure_count == nums_columns[i] - wall_count {
-            for j in 0..8 {
-                if matrix[j][i] == Tile::Unsure {
-                    matrix[j][i] = Tile::Wall;
-                }
-            }
-        }
-        if wall_count == nums_columns[i] && unsure_count > 0 {
-            for j in 0..8 {
-                if matrix[j][i] == Tile::Unsure {
-                    matrix[j][i] = Tile::Ground;
-                }
-            }
-        }
-    }
+    // focus window
+    let mut enigo = Enigo::new(&Settings::default()).unwrap();
+    let (x, y) = monitor.to_input((5, 5));
+    enigo.move_mouse(x, y, Coordinate::Abs).unwrap();
+    sleep(Duration::from_millis(50));
+    enigo.button(enigo::Button::Left, enigo::Direction::Click).unwrap();
+    sleep(Duration::from_millis(50));
 
-    // collapse monster escape routes
+    // execute solution
     for y in 0..8 {
         for x in 0..8 {
-            if matrix[y][x] == Tile::Monster {
-                let mut walls = 0;
-                for (dx, dy) in directions {
-                    let nx = (x as i32 + dx) as usize;
-                    let ny = (y as i32 + dy) as usize;
-                    if let Some(row) = matrix.get(ny) {
-                        if let Some(tile) = row.get(nx) {
-                            if tile == &Tile::Wall {
-                                walls += 1;
-                            }
-                        } else {
-                            walls += 1;
-                        }
-                    } else {
-                        walls += 1;
-                    }
-                }
-                if walls == 3 {
-                    for (dx, dy) in directions {
-                        let nx = (x as i32 + dx) as usize;
-                        let ny = (y as i32 + dy) as usize;
-                        if let Some(row) = matrix.get_mut(ny) {
-                            if let Some(tile) = row.get_mut(nx) {
-                                if tile != &Tile::Wall {
-                                    *tile = Tile::Ground;
-                                }
-                            }
-                        }
-                    }
-                }
+            for button in clicks(&on_board[y][x], &matrix[y][x], clear_marks) {
+                let (cx, cy) = monitor.to_input(layout.tile_center(x, y));
+                enigo.move_mouse(cx, cy, Coordinate::Abs).unwrap();
+                sleep(Duration::from_millis(50));
+                enigo.button(button, enigo::Direction::Click).unwrap();
+                sleep(Duration::from_millis(50));
             }
         }
     }
 
-    // collapse monster wall enclosures
-    for y in 0..8 {
-        for x in 0..8 {
-            if matrix[y][x] == Tile::Monster {
-                let mut escape_route = false;
-                for (dx, dy) in directions {
-                    let nx = (x as i32 + dx) as usize;
-                    let ny = (y as i32 + dy) as usize;
-                    if let Some(row) = matrix.get(ny) {
-                        if let Some(tile) = row.get(nx) {
-                            if tile == &Tile::Ground {
-                                escape_route = true;
-                                break;
-                            }
-                        }
-                    }
-                }
-                if escape_route {
-                    for (dx, dy) in directions {
-                        let nx = (x as i32 + dx) as usize;
-                        let ny = (y as i32 + dy) as usize;
-                        if let Some(row) = matrix.get_mut(ny) {
-                            if let Some(tile) = row.get_mut(nx) {
-                                if tile != &Tile::Ground {
-                                    *tile = Tile::Wall;
-                                }
-                            }
-                        }
-                    }
-                }
+    // the game grays out the clue of every line with the right walls, so once they all are it took
+    sleep(Duration::from_millis(200));
+    let (origin, size) = layout.bounds();
+    let snapshot = monitor.snapshot(origin, size).unwrap_or_else(|error| {
+        eprintln!("{error}");
+        exit(1);
+    });
+    match read_clues(&snapshot, &layout, &templates) {
+        Ok((rows, columns)) => {
+            let unsatisfied: Vec<String> = (0..8).map(Line::Row).zip(rows.iter())
+                .chain((0..8).map(Line::Column).zip(columns.iter()))
+                .filter(|(_, clue)| !clue.satisfied)
+                .map(|(line, _)| line.to_string())
+                .collect();
+            if !unsatisfied.is_empty() {
+                eprintln!("the game doesn't agree about {}, some clicks might not have landed", unsatisfied.join(", "));
+                exit(1);
             }
-        }
+            println!("every row and column is satisfied");
+        },
+        Err(unreadable) => {
+            eprintln!("couldn't read the clues again to check the solution took:");
+            for (line, error) in unreadable {
+                eprintln!("  the clue for {line} {error}");
+            }
+            exit(1);
+        },
     }
+}
 
-    // rows and cols with a chest always have at least 2 ground tiles
-    for y in 0..8 {
-        for x in 0..8 {
-            if matrix[y][x] == Tile::Chest {
-                // wall is right next to chest
-                for (dx, dy) in directions {
-                    let nx = (x as i32 + dx) as usize;
-                    let ny = (y as i32 + dy) as usize;
-                    if let Some(row) = matrix.get(ny) {
-                        if let Some(tile) = row.get(nx) {
-                            if tile == &Tile::Wall {
-                                let mx = (x as i32 - dx) as usize;
-                                let my = (y as i32 - dy) as usize;
-                                let mx2 = (x as i32 - dx * 2) as usize;
-                                let my2 = (y as i32 - dy * 2) as usize;
-                                matrix[my][mx] = Tile::Ground;
-                                matrix[my2][mx2] = Tile::Ground;
-                            }
-                        } else {
-                            let mx = (x as i32 - dx) as usize;
-                            let my = (y as i32 - dy) as usize;
-                            let mx2 = (x as i32 - dx * 2) as usize;
-                            let my2 = (y as i32 - dy * 2) as usize;
-                            matrix[my][mx] = Tile::Ground;
-                            matrix[my2][mx2] = Tile::Ground;
-                        }
-                    } else {
-                        let mx = (x as i32 - dx) as usize;
-                        let my = (y as i32 - dy) as usize;
-                        let mx2 = (x as i32 - dx * 2) as usize;
-                        let my2 = (y as i32 - dy * 2) as usize;
-                        matrix[my][mx] = Tile::Ground;
-                        matrix[my2][mx2] = Tile::Ground;
-                    }
-                }
+// clues of the rows and of the columns
+type Clues = (Vec<ClueRead>, Vec<ClueRead>);
 
-                // wall is gapped from the chest
-                for (dx, dy) in directions {
-                    let nx = (x as i32 + dx * 2) as usize;
-                    let ny = (y as i32 + dy * 2) as usize;
-                    if let Some(row) = matrix.get(ny) {
-                        if let Some(tile) = row.get(nx) {
-                            if tile == &Tile::Wall {
-                                let mx = (x as i32 - dx) as usize;
-                                let my = (y as i32 - dy) as usize;
-                                matrix[my][mx] = Tile::Ground;
-                            }
-                        } else {
-                            let mx = (x as i32 - dx) as usize;
-                            let my = (y as i32 - dy) as usize;
-                            matrix[my][mx] = Tile::Ground;
-                        }
-                    } else {
-                        let mx = (x as i32 - dx) as usize;
-                        let my = (y as i32 - dy) as usize;
-                        matrix[my][mx] = Tile::Ground;
-                    }
-                }
+// every clue, or which ones couldn't be read and why
+fn read_clues(snapshot: &Snapshot, layout: &Layout, templates: &Templates) -> Result<Clues, Vec<(Line, String)>> {
+    let clue_size = (layout.tile_size, layout.tile_size);
+    let mut rows = vec![];
+    let mut columns = vec![];
+    let mut unreadable = vec![];
+    for i in 0..8 {
+        for (line, crop, clues) in [
+            (Line::Row(i), layout.row_clue(i), &mut rows),
+            (Line::Column(i), layout.column_clue(i), &mut columns),
+        ] {
+            match snapshot.crop(crop, clue_size).and_then(|image| recognition::read_clue(&image, templates)) {
+                Ok(clue) => clues.push(clue),
+                Err(error) => unreadable.push((line, error)),
             }
         }
     }
+    if unreadable.is_empty() {
+        Ok((rows, columns))
+    } else {
+        Err(unreadable)
+    }
+}
 
-    // let's set some ground rules
-    for y in 0..8 {
-        for x in 0..8 {
-            if matrix[y][x] == Tile::Ground {
-                let mut walls = 0;
-                let mut monsters = 0;
-                let mut unsures = 0;
-                for (dx, dy) in directions {
-                    let nx = (x as i32 + dx) as usize;
-                    let ny = (y as i32 + dy) as usize;
-                    if let Some(row) = matrix.get(ny) {
-                        if let Some(tile) = row.get(nx) {
-                            match tile {
-                                Tile::Unsure => unsures += 1,
-                                Tile::Chest => (),
-                                Tile::Monster => monsters += 1,
-                                Tile::Ground => (),
-                                Tile::Wall => walls += 1,
-                            }
-                        } else {
-                            walls += 1;
-                        }
-                    } else {
-                        walls += 1;
-                    }
-                }
-                if unsures != 0 && (monsters == 3 || walls == 2) {
-                    // replace unsure with ground
-                    for (dx, dy) in directions {
-                        let nx = (x as i32 + dx) as usize;
-                        let ny = (y as i32 + dy) as usize;
-                        if let Some(row) = matrix.get_mut(ny) {
-                            if let Some(tile) = row.get_mut(nx) {
-                                if tile == &Tile::Unsure {
-                                    *tile = Tile::Ground
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
+// what to click to turn a tile on the board into the solution's,
+// left click puts down or takes away a wall, right click does the same for a mark
+fn clicks(on_board: &Tile, solution: &Tile, clear_marks: bool) -> Vec<enigo::Button> {
+    use enigo::Button::{Left, Right};
+    match (on_board, solution) {
+        (Tile::Wall, Tile::Wall) => vec![],
+        (Tile::Wall, _) => vec![Left],
+        (Tile::Ground, Tile::Wall) => vec![Right, Left],
+        (Tile::Ground, _) if clear_marks => vec![Right],
+        (_, Tile::Wall) => vec![Left],
+        _ => vec![],
     }
 }
 
-fn debug_print(matrix: &Vec<Vec<Tile>>, nums_columns: &Vec<usize>, nums_rows: &Vec<usize>) {
-    print!(" ");
-    for col_num in nums_columns {
-        print!("{col_num}");
+fn check_file(path: &str) {
+    let text = std::fs::read_to_string(path).unwrap_or_else(|error| {
+        eprintln!("couldn't read {path}: {error}");
+        exit(2);
+    });
+    let (board, nums_columns, nums_rows) = parse_board(&text).unwrap_or_else(|error| {
+        eprintln!("couldn't read {path}: {error}");
+        exit(2);
+    });
+    let problems = lint::lint(&board, &nums_columns, &nums_rows).unwrap_or_else(|error| {
+        eprintln!("couldn't check {path}: {error}");
+        exit(2);
+    });
+    if !problems.is_empty() {
+        for problem in problems.iter() {
+            eprintln!("{problem}");
+        }
+        exit(2);
     }
-    println!();
-    for (i, row) in matrix.iter().enumerate() {
-        print!("{}", nums_rows[i]);
-        for item in row {
-            match item {
-                Tile::Chest => print!("O"),
-                Tile::Unsure => print!("?"),
-                Tile::Monster => print!("!"),
-                Tile::Ground => print!("_"),
-                Tile::Wall => print!("#"),
-            }
-        }
-        println!();
+    let report = check::check(&board, &nums_columns, &nums_rows).unwrap_or_else(|error| {
+        eprintln!("couldn't check {path}: {error}");
+        exit(2);
+    });
+    match report.solutions {
+        0 => {
+            eprintln!("the puzzle itself has no solution");
+            exit(2);
+        },
+        1 => println!("the puzzle has a single solution"),
+        solutions if report.capped => println!("the puzzle has at least {solutions} solutions, only those were checked against"),
+        solutions => println!("the puzzle has {solutions} solutions"),
+    }
+    if report.mistakes.is_empty() {
+        println!("no mistakes so far");
+        return;
+    }
+    for mistake in report.mistakes.iter() {
+        println!("{mistake}");
     }
+    exit(1);
 }
 
-fn detect_number(image: ImageBuffer<Rgba<u8>, Vec<u8>>) -> usize {
-    for i in 0..8 {
-        let comparison_image = Reader::open(format!("assets/numbers/{i}.png")).unwrap().decode().unwrap();
-        for dx in -5..=5 {
-            for dy in -5..=5 {
-                let mut diff = 0;
-                let mut comparison_image_shifted = ImageBuffer::from_pixel(
-                    comparison_image.width(),
-                    comparison_image.height(),
-                    Rgba(WHITE),
-                );
-                overlay(&mut comparison_image_shifted, &comparison_image, dx, dy);
-                for x in 0..image.width() {
-                    for y in 0..image.height() {
-                        let comparison_pixel = comparison_image_shifted.get_pixel(x, y).0;
-                        let pixel = image.get_pixel(x, y).0;
-                        if comparison_pixel == BLACK && !(pixel == ORANGE || pixel == GRAY) {
-                            diff += 1;
-                        }
-                    }
-                }
-                if diff == 0 {
-                    return i;
-                }
-            }
-        }
+// value following a command line flag, e.g. `--threads 4`
+fn arg_value(name: &str) -> Option<String> {
+    let mut args = std::env::args().skip_while(|arg| arg != name);
+    args.next()?;
+    args.next()
+}
+
+// number following a command line flag, exits if it isn't one
+fn arg_number<T: std::str::FromStr>(name: &str) -> Option<T> {
+    let value = arg_value(name)?;
+    match value.parse() {
+        Ok(number) => Some(number),
+        Err(_) => {
+            eprintln!("{name} takes a number, `{value}` isn't one");
+            exit(2);
+        },
     }
-    8
+}
+
+// values following every use of a repeatable flag
+fn arg_values(name: &str) -> Vec<String> {
+    let args: Vec<String> = std::env::args().collect();
+    args.windows(2).filter(|pair| pair[0] == name).map(|pair| pair[1].clone()).collect()
 }
@@ -0,0 +1,122 @@
+use std::fmt;
+
+use screenshots::{image::{imageops, RgbaImage}, Screen};
+
+// the screen the game is on
+//
+// everything else works in pixels of a screenshot of this screen, like the layout does. this turns
+// those into the scaled coordinates capturing expects and the global ones the mouse gets moved to.
+pub struct Monitor {
+    pub screen: Screen,
+}
+
+impl Monitor {
+    // a screenshot of the whole screen
+    pub fn capture(&self) -> Result<RgbaImage, String> {
+        self.screen.capture().map_err(|error| format!("couldn't capture the screen: {error}"))
+    }
+
+    // a screenshot of part of the screen, position and size in screenshot pixels, and where its
+    // top left corner ended up
+    //
+    // capturing goes by unscaled pixels, so on a scaled screen the area gets widened out to the
+    // unscaled ones around it and can start a little before the position asked for.
+    pub fn capture_area(&self, (x, y): (i32, i32), (width, height): (i32, i32)) -> Result<((i32, i32), RgbaImage), String> {
+        let scale = self.scale();
+        let (left, top) = ((x as f32 / scale).floor() as i32, (y as f32 / scale).floor() as i32);
+        let (right, bottom) = (((x + width) as f32 / scale).ceil() as i32, ((y + height) as f32 / scale).ceil() as i32);
+        let image = self.screen.capture_area(left, top, (right - left) as u32, (bottom - top) as u32)
+            .map_err(|error| format!("couldn't capture the screen: {error}"))?;
+        Ok((((left as f32 * scale).round() as i32, (top as f32 * scale).round() as i32), image))
+    }
+
+    // one capture of an area that everything in it gets cropped out of,
+    // rather than a trip to the display server for every crop
+    pub fn snapshot(&self, origin: (i32, i32), size: (i32, i32)) -> Result<Snapshot, String> {
+        let (captured, image) = self.capture_area(origin, size)?;
+        let snapshot = Snapshot { origin: captured, image };
+        if !snapshot.covers(origin, size) {
+            return Err(format!(
+                "asked to capture {}x{} at {}, {} but got {}x{} at {}, {}, is the board partly off the screen?",
+                size.0, size.1, origin.0, origin.1, snapshot.image.width(), snapshot.image.height(), captured.0, captured.1,
+            ));
+        }
+        Ok(snapshot)
+    }
+
+    // where a screenshot pixel is for the mouse, the screen's position is scaled the same way
+    // its screenshots are
+    pub fn to_input(&self, (x, y): (i32, i32)) -> (i32, i32) {
+        let info = self.screen.display_info;
+        ((info.x as f32 * info.scale_factor) as i32 + x, (info.y as f32 * info.scale_factor) as i32 + y)
+    }
+
+    fn scale(&self) -> f32 {
+        self.screen.display_info.scale_factor
+    }
+}
+
+impl fmt::Display for Monitor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let info = self.screen.display_info;
+        write!(f, "id {}, {}x{} at {}, {}, scaled {}", info.id, info.width, info.height, info.x, info.y, info.scale_factor)?;
+        if info.is_primary {
+            write!(f, ", primary")?;
+        }
+        Ok(())
+    }
+}
+
+// part of the screen captured at one moment
+pub struct Snapshot {
+    // where the image's top left corner is, in screenshot pixels
+    pub origin: (i32, i32),
+    pub image: RgbaImage,
+}
+
+impl Snapshot {
+    // part of the snapshot, position and size in screenshot pixels like for capture_area,
+    // none of it can be outside what got captured
+    pub fn crop(&self, (x, y): (i32, i32), (width, height): (i32, i32)) -> Result<RgbaImage, String> {
+        if !self.covers((x, y), (width, height)) {
+            return Err(format!("is at {x}, {y}, outside what got captured"));
+        }
+        let (left, top) = ((x - self.origin.0) as u32, (y - self.origin.1) as u32);
+        Ok(imageops::crop_imm(&self.image, left, top, width as u32, height as u32).to_image())
+    }
+
+    fn covers(&self, (x, y): (i32, i32), (width, height): (i32, i32)) -> bool {
+        let (left, top) = (x - self.origin.0, y - self.origin.1);
+        left >= 0 && top >= 0 && width >= 0 && height >= 0
+            && (left + width) as u32 <= self.image.width() && (top + height) as u32 <= self.image.height()
+    }
+}
+
+pub fn all() -> Result<Vec<Monitor>, String> {
+    let screens = Screen::all().map_err(|error| format!("couldn't list the screens: {error}"))?;
+    Ok(screens.into_iter().map(|screen| Monitor { screen }).collect())
+}
+
+// `primary`, a position in the list of screens or a screen's id, the first screen if none is given
+pub fn pick(choice: Option<&str>) -> Result<Monitor, String> {
+    let mut monitors = all()?;
+    if monitors.is_empty() {
+        return Err("there are no screens to capture".to_string());
+    }
+    let index = match choice {
+        None => Some(0),
+        Some("primary") => monitors.iter().position(|monitor| monitor.screen.display_info.is_primary),
+        Some(choice) => {
+            let number: u32 = choice.parse().map_err(|_| format!("`{choice}` isn't a screen, use primary, a position or an id"))?;
+            if (number as usize) < monitors.len() {
+                Some(number as usize)
+            } else {
+                monitors.iter().position(|monitor| monitor.screen.display_info.id == number)
+            }
+        },
+    };
+    match index {
+        Some(index) => Ok(monitors.swap_remove(index)),
+        None => Err(format!("there's no screen {}, --list-screens shows them all", choice.unwrap_or_default())),
+    }
+}
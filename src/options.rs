@@ -0,0 +1,66 @@
+use std::{sync::{atomic::{AtomicBool, Ordering}, Arc}, time::Instant};
+
+use crate::{progress::{SolveEvent, SolveObserver}, rules::RuleSet, tree::SearchTree};
+
+// shared flag asking a running solve to stop as soon as it can
+#[derive(Clone, Default)]
+pub struct CancelToken {
+    flag: Arc<AtomicBool>,
+    parent: Option<Box<CancelToken>>,
+}
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.flag.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.flag.load(Ordering::Relaxed) || self.parent.as_ref().is_some_and(|parent| parent.is_cancelled())
+    }
+
+    // token that can be cancelled on its own, and also is whenever this one is
+    pub fn child(&self) -> CancelToken {
+        CancelToken {
+            flag: Arc::default(),
+            parent: Some(Box::new(self.clone())),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct SolveOptions {
+    // give up after expanding this many matrices
+    pub node_limit: Option<u64>,
+    // give up once this point in time has passed
+    pub deadline: Option<Instant>,
+    pub cancel: CancelToken,
+    // worker threads exploring branches, 1 searches on the calling thread
+    pub threads: usize,
+    pub observer: Option<Arc<dyn SolveObserver>>,
+    // records every board the search looks at, for drawing with graphviz
+    pub tree: Option<Arc<SearchTree>>,
+    // certainty collapses to run between branches, leaving some out makes the search branch more
+    pub rules: RuleSet,
+}
+
+impl Default for SolveOptions {
+    fn default() -> Self {
+        SolveOptions {
+            node_limit: None,
+            deadline: None,
+            cancel: CancelToken::default(),
+            threads: 1,
+            observer: None,
+            tree: None,
+            rules: RuleSet::default(),
+        }
+    }
+}
+
+impl SolveOptions {
+    pub fn notify(&self, event: &SolveEvent) {
+        if let Some(observer) = &self.observer {
+            observer.on_event(event);
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use screenshots::image::{Rgba, RgbaImage};
+
+use crate::{layout::{Layout, CAPTURE_SIZE}, monitor::Snapshot, recognition::{self, Kind, Recognition, Templates}};
+
+// what got read is drawn in green, what couldn't be in red, on a black backdrop so it shows up
+// on any background
+const READ: [u8; 4] = [80, 230, 80, 255];
+const MISREAD: [u8; 4] = [255, 60, 60, 255];
+const BACKDROP: [u8; 4] = [0, 0, 0, 255];
+
+// the snapshot with every tile's crop box, what it was taken for and how sure that was,
+// and every clue's box with the number read from it
+//
+// clues get read again here rather than passed in, so they show up even when some couldn't be read.
+pub fn draw(snapshot: &Snapshot, layout: &Layout, tiles: &[Vec<Recognition>], templates: &Templates) -> RgbaImage {
+    let mut image = snapshot.image.clone();
+    let at = |(x, y): (i32, i32)| (x - snapshot.origin.0, y - snapshot.origin.1);
+    // letters are 3x5 pixels, scaled up to about a tenth of a tile
+    let scale = (layout.tile_size / 32).max(1);
+
+    for (y, row) in tiles.iter().enumerate() {
+        for (x, recognition) in row.iter().enumerate() {
+            let (read, label, colour) = match recognition {
+                Recognition::Recognized(read) => (read, name(read.kind), READ),
+                Recognition::Unrecognized(read) => (read, "unknown", MISREAD),
+            };
+            let (left, top) = at(layout.tile_crop(x, y));
+            outline(&mut image, (left - 1, top - 1), (CAPTURE_SIZE + 2, CAPTURE_SIZE + 2), colour);
+            let corner = at((layout.tile_x + x as i32 * layout.tile_size + 2, layout.tile_y + y as i32 * layout.tile_size + 2));
+            text(&mut image, corner, label, colour, scale);
+            text(&mut image, (corner.0, corner.1 + 6 * scale), &format!("{:.0}%", read.confidence * 100.0), colour, scale);
+        }
+    }
+
+    let clue_size = (layout.tile_size, layout.tile_size);
+    for i in 0..8 {
+        for crop in [layout.row_clue(i), layout.column_clue(i)] {
+            let (label, colour) = match snapshot.crop(crop, clue_size).and_then(|image| recognition::read_clue(&image, templates)) {
+                Ok(clue) => (format!("{} {:.0}%", clue.number, clue.score * 100.0), READ),
+                Err(_) => ("?".to_string(), MISREAD),
+            };
+            let (left, top) = at(crop);
+            outline(&mut image, (left, top), clue_size, colour);
+            text(&mut image, (left + 2, top + 2), &label, colour, scale);
+        }
+    }
+    image
+}
+
+fn name(kind: Kind) -> &'static str {
+    match kind {
+        Kind::Ground => "ground",
+        Kind::Chest => "chest",
+        Kind::Monster => "monster",
+        Kind::Wall => "wall",
+        Kind::Mark => "mark",
+    }
+}
+
+fn outline(image: &mut RgbaImage, (left, top): (i32, i32), (width, height): (i32, i32), colour: [u8; 4]) {
+    for i in 0..width {
+        put(image, left + i, top, colour);
+        put(image, left + i, top + height - 1, colour);
+    }
+    for i in 0..height {
+        put(image, left, top + i, colour);
+        put(image, left + width - 1, top + i, colour);
+    }
+}
+
+// text in capitals whatever case it's given in, anything the font doesn't have comes out as ?
+fn text(image: &mut RgbaImage, (left, top): (i32, i32), text: &str, colour: [u8; 4], scale: i32) {
+    let width = text.chars().count() as i32 * 4 * scale + scale;
+    for y in top..top + 7 * scale {
+        for x in left..left + width {
+            put(image, x, y, BACKDROP);
+        }
+    }
+    for (i, letter) in text.chars().enumerate() {
+        let rows = glyph(letter.to_ascii_uppercase()).or_else(|| glyph('?')).unwrap();
+        let letter_left = left + scale + i as i32 * 4 * scale;
+        for (row, bits) in rows.iter().enumerate() {
+            for column in 0..3 {
+                if bits & (0b100 >> column) == 0 {
+                    continue;
+                }
+                for dy in 0..scale {
+                    for dx in 0..scale {
+                        put(image, letter_left + column * scale + dx, top + scale + row as i32 * scale + dy, colour);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// rows of a 3x5 letter from the top, the highest of the three bits being the left pixel
+fn glyph(letter: char) -> Option<[u8; 5]> {
+    Some(match letter {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b001, 0b001],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        '%' => [0b101, 0b001, 0b010, 0b100, 0b101],
+        '?' => [0b111, 0b001, 0b011, 0b000, 0b010],
+        ' ' => [0b000, 0b000, 0b000, 0b000, 0b000],
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        _ => return None,
+    })
+}
+
+// pixels off the image are left out, boxes and labels near the edge get cut off
+fn put(image: &mut RgbaImage, x: i32, y: i32, colour: [u8; 4]) {
+    if x >= 0 && y >= 0 && (x as u32) < image.width() && (y as u32) < image.height() {
+        image.put_pixel(x as u32, y as u32, Rgba(colour));
+    }
+}
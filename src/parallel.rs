@@ -0,0 +1,143 @@
+use std::{collections::VecDeque, sync::{atomic::{AtomicBool, AtomicUsize, Ordering}, Mutex}, thread};
+
+use crate::{count_unsure, expand, is_solved, progress::SolveEvent, tree::NodeStatus, zobrist, Branch, Search, Tile};
+
+// how many levels of branches get split up front before the workers take over
+const SPLIT_DEPTH: usize = 2;
+
+// same search as explore(), with branches spread across worker threads
+//
+// every worker keeps its own queue of matrices, working the most certain ones first
+// and pushing their branches to the front. idle workers steal from the back of
+// other queues, which is where the bigger, less explored chunks of work are.
+pub fn explore_parallel(search: &Search, matrix: &[Vec<Tile>], root: Option<usize>) -> Option<Vec<Vec<Tile>>> {
+    let threads = search.options.threads;
+    let found = AtomicBool::new(false);
+    let solution = Mutex::new(None);
+    let report = |collapse: &Branch| {
+        if is_solved(&collapse.matrix, search.nums_columns, search.nums_rows) && !found.swap(true, Ordering::Relaxed) {
+            search.mark(collapse.node, NodeStatus::Solved);
+            *solution.lock().unwrap() = Some(collapse.matrix.clone());
+        }
+    };
+
+    // split top level branches, going a level deeper if there's too few to go around
+    let mut branches = vec![Branch {
+        matrix: matrix.to_vec(),
+        certainty: 64 - count_unsure(matrix),
+        hash: zobrist::hash(matrix),
+        depth: 0,
+        node: root,
+    }];
+    for _ in 0..SPLIT_DEPTH {
+        if branches.len() >= threads {
+            break;
+        }
+        let mut next_branches = vec![];
+        for branch in branches.iter() {
+            if !search.visit(&branch.matrix, branch.certainty, branch.depth) {
+                return None;
+            }
+            for collapse in expand(search, &branch.matrix, branch.hash, branch.depth, branch.node) {
+                if collapse.certainty == 64 {
+                    report(&collapse);
+                } else {
+                    next_branches.push(collapse);
+                }
+            }
+        }
+        branches = next_branches;
+    }
+    if found.load(Ordering::Relaxed) {
+        return solution.into_inner().unwrap();
+    }
+
+    // deal the branches out round robin, best ones first
+    let queues: Vec<Mutex<VecDeque<Branch>>> = (0..threads).map(|_| Mutex::default()).collect();
+    let pending = AtomicUsize::new(branches.len());
+    for (i, branch) in branches.into_iter().enumerate() {
+        queues[i % threads].lock().unwrap().push_back(branch);
+    }
+
+    thread::scope(|scope| {
+        for worker in 0..threads {
+            let (queues, pending, found, report) = (&queues, &pending, &found, &report);
+            scope.spawn(move || {
+                loop {
+                    if found.load(Ordering::Relaxed) || search.stopped() {
+                        break;
+                    }
+                    let task = queues[worker].lock().unwrap().pop_front().or_else(|| steal(queues, worker));
+                    let Some(task) = task else {
+                        // nothing left anywhere, and nobody working on something that might produce more
+                        if pending.load(Ordering::Acquire) == 0 {
+                            break;
+                        }
+                        thread::yield_now();
+                        continue;
+                    };
+                    if !search.visit(&task.matrix, task.certainty, task.depth) {
+                        break;
+                    }
+                    search.options.notify(&SolveEvent::Branch {
+                        depth: task.depth,
+                        matrix: &task.matrix,
+                        unsure_count: 64 - task.certainty,
+                    });
+                    let collapses = expand(search, &task.matrix, task.hash, task.depth, task.node);
+                    if collapses.is_empty() {
+                        // only dead ends get marked here, the rest of a task's branches end up spread across workers
+                        search.past_matrices().store(task.hash, 64 - task.certainty, true);
+                        search.options.notify(&SolveEvent::Backtrack { depth: task.depth });
+                    }
+                    // pushed in reverse so the most certain collapse ends up at the front
+                    for collapse in collapses.into_iter().rev() {
+                        if collapse.certainty == 64 {
+                            report(&collapse);
+                        } else {
+                            pending.fetch_add(1, Ordering::AcqRel);
+                            queues[worker].lock().unwrap().push_front(collapse);
+                        }
+                    }
+                    pending.fetch_sub(1, Ordering::AcqRel);
+                }
+            });
+        }
+    });
+
+    solution.into_inner().unwrap()
+}
+
+fn steal(queues: &[Mutex<VecDeque<Branch>>], worker: usize) -> Option<Branch> {
+    (1..queues.len())
+        .map(|offset| (worker + offset) % queues.len())
+        .find_map(|victim| queues[victim].lock().unwrap().pop_back())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{options::SolveOptions, parse_board, solve};
+
+    const PUZZLE: &str = "
+         23243335
+        1???????!
+        4?O??????
+        0???????!
+        6????????
+        1!???????
+        6????????
+        2!??????!
+        5????????
+    ";
+
+    // splitting up the root visits it, so the serial search has to count it too
+    #[test]
+    fn counts_the_same_as_the_serial_search() {
+        let (matrix, nums_columns, nums_rows) = parse_board(PUZZLE).unwrap();
+        let serial = solve(&matrix, &nums_columns, &nums_rows, &SolveOptions { threads: 1, ..Default::default() });
+        let parallel = solve(&matrix, &nums_columns, &nums_rows, &SolveOptions { threads: 4, ..Default::default() });
+        assert_eq!(serial.stats.nodes, 1);
+        assert_eq!(parallel.stats.nodes, 1);
+        assert!(serial.stats.propagation_rounds > 0);
+    }
+}
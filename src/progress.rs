@@ -0,0 +1,99 @@
+use std::{sync::Mutex, time::{Duration, Instant}};
+
+use crate::Tile;
+
+// how often the progress line gets redrawn at most
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+pub enum SolveEvent<'a> {
+    // one round of certainty collapses went through
+    Propagation { matrix: &'a [Vec<Tile>], unsure_count: usize },
+    // a collapse got picked and is about to be explored
+    Branch { depth: usize, matrix: &'a [Vec<Tile>], unsure_count: usize },
+    // every collapse below this depth failed, going back up
+    Backtrack { depth: usize },
+}
+
+// gets told what the solver is up to, called from every thread the solver uses
+pub trait SolveObserver: Send + Sync {
+    fn on_event(&self, event: &SolveEvent);
+}
+
+// live status line on stderr
+pub struct ProgressLine {
+    start: Instant,
+    state: Mutex<ProgressState>,
+}
+
+#[derive(Default)]
+struct ProgressState {
+    last_draw: Option<Instant>,
+    rounds: u64,
+    branches: u64,
+    backtracks: u64,
+    depth: usize,
+    unsure_count: usize,
+    wall_count: usize,
+}
+
+impl Default for ProgressLine {
+    fn default() -> Self {
+        ProgressLine {
+            start: Instant::now(),
+            state: Mutex::default(),
+        }
+    }
+}
+
+impl ProgressLine {
+    // moves past the status line so it doesn't get overwritten
+    pub fn finish(&self) {
+        self.draw(&self.state.lock().unwrap());
+        eprintln!();
+    }
+
+    fn draw(&self, state: &ProgressState) {
+        eprint!(
+            "\r{:>8.1?}  depth {:>2}  unsure {:>2}  walls {:>2}  branches {}  backtracks {}  rounds {}",
+            self.start.elapsed(),
+            state.depth,
+            state.unsure_count,
+            state.wall_count,
+            state.branches,
+            state.backtracks,
+            state.rounds,
+        );
+    }
+}
+
+impl SolveObserver for ProgressLine {
+    fn on_event(&self, event: &SolveEvent) {
+        let mut state = self.state.lock().unwrap();
+        match *event {
+            SolveEvent::Propagation { matrix, unsure_count } => {
+                state.rounds += 1;
+                state.unsure_count = unsure_count;
+                state.wall_count = count_walls(matrix);
+            },
+            SolveEvent::Branch { depth, matrix, unsure_count } => {
+                state.branches += 1;
+                state.depth = depth;
+                state.unsure_count = unsure_count;
+                state.wall_count = count_walls(matrix);
+            },
+            SolveEvent::Backtrack { depth } => {
+                state.backtracks += 1;
+                state.depth = depth;
+            },
+        }
+        let now = Instant::now();
+        if state.last_draw.is_none_or(|last_draw| now - last_draw >= REDRAW_INTERVAL) {
+            state.last_draw = Some(now);
+            self.draw(&state);
+        }
+    }
+}
+
+fn count_walls(matrix: &[Vec<Tile>]) -> usize {
+    matrix.iter().flatten().filter(|tile| tile == &&Tile::Wall).count()
+}
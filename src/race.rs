@@ -0,0 +1,115 @@
+use std::{sync::mpsc, thread, time::{Duration, Instant}};
+
+use crate::{first_unsure, is_solved, options::SolveOptions, settle, solve, solve_cells, stats::SolveStats, SolveReport, SolveResult, Tile};
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Strategy {
+    // certainty collapses followed by structure and random collapses
+    Search,
+    // certainty collapses only, gives up if anything is left unsure
+    Propagation,
+    // depth first search over single tiles
+    CellSearch,
+}
+
+impl Strategy {
+    pub const ALL: [Strategy; 3] = [Strategy::Search, Strategy::Propagation, Strategy::CellSearch];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Strategy::Search => "search",
+            Strategy::Propagation => "propagation",
+            Strategy::CellSearch => "cell-search",
+        }
+    }
+
+    // the searches settle the matrix themselves, counting it in their stats
+    fn run(&self, matrix: &[Vec<Tile>], nums_columns: &[usize], nums_rows: &[usize], options: &SolveOptions) -> SolveReport {
+        match self {
+            Strategy::Search => solve(matrix, nums_columns, nums_rows, options),
+            Strategy::CellSearch => solve_cells(matrix, nums_columns, nums_rows, options),
+            Strategy::Propagation => {
+                let mut matrix = matrix.to_vec();
+                let result = if !settle(&mut matrix, nums_columns, nums_rows, options) {
+                    SolveResult::Unsolvable
+                } else if first_unsure(&matrix).is_none() {
+                    SolveResult::Solved(matrix)
+                } else {
+                    SolveResult::Exhausted(matrix)
+                };
+                SolveReport {
+                    result,
+                    stats: SolveStats::default(),
+                }
+            },
+        }
+    }
+}
+
+pub struct Attempt {
+    pub strategy: Strategy,
+    pub elapsed: Duration,
+    pub result: SolveResult,
+    pub stats: SolveStats,
+}
+
+pub struct RaceResult {
+    // first validated solution and the strategy that found it
+    pub winner: Option<(Strategy, Vec<Vec<Tile>>)>,
+    // every strategy in the order they finished
+    pub attempts: Vec<Attempt>,
+}
+
+// runs every strategy on its own thread, the first validated solution or proof that there is none
+// cancels the rest
+pub fn race(matrix: &[Vec<Tile>], nums_columns: &[usize], nums_rows: &[usize], strategies: &[Strategy], options: &SolveOptions) -> RaceResult {
+    let options = SolveOptions {
+        cancel: options.cancel.child(),
+        ..options.clone()
+    };
+    let (sender, receiver) = mpsc::channel();
+    thread::scope(|scope| {
+        for &strategy in strategies {
+            let sender = sender.clone();
+            let options = &options;
+            scope.spawn(move || {
+                let start = Instant::now();
+                let mut report = strategy.run(matrix, nums_columns, nums_rows, options);
+                let decisive = match &report.result {
+                    SolveResult::Solved(solution) => is_solved(solution, nums_columns, nums_rows),
+                    // a strategy that got stopped by a limit or the others gives up instead,
+                    // so this one ran to the end and proved it
+                    SolveResult::Unsolvable => true,
+                    SolveResult::Exhausted(_) => false,
+                };
+                if decisive {
+                    options.cancel.cancel();
+                } else if let SolveResult::Solved(solution) = &report.result {
+                    // a broken solution doesn't prove anything either way
+                    report.result = SolveResult::Exhausted(solution.clone());
+                }
+                sender.send((strategy, start.elapsed(), report)).unwrap();
+            });
+        }
+    });
+    drop(sender);
+
+    let mut result = RaceResult {
+        winner: None,
+        attempts: vec![],
+    };
+    for (strategy, elapsed, report) in receiver {
+        if result.winner.is_none() {
+            if let SolveResult::Solved(solution) = &report.result {
+                result.winner = Some((strategy, solution.clone()));
+            }
+        }
+        result.attempts.push(Attempt {
+            strategy,
+            elapsed,
+            result: report.result,
+            stats: report.stats,
+        });
+    }
+    result
+}
@@ -0,0 +1,364 @@
+use std::{fmt, fs};
+
+use screenshots::image::{self, RgbaImage};
+use last_call_bbs_dnd_solver::Tile;
+
+// how sure a tile has to be to go ahead with it, --threshold changes it
+pub const DEFAULT_THRESHOLD: f32 = 0.8;
+
+// how far apart two colours can be and still count as the same,
+// gamma, compression and the crt effect all move them a little
+const COLOUR_TOLERANCE: u32 = 40;
+
+// colours clue numbers get drawn in, gray once the line has as many walls as it needs
+const ORANGE: [u8; 4] = [250, 91, 69, 255];
+const GRAY: [u8; 4] = [98, 91, 77, 255];
+// colour of the number in the templates, the rest of them is white
+const BLACK: [u8; 4] = [0, 0, 0, 255];
+
+// how well a clue has to match its number, and how much better than any other number
+const MIN_CLUE_SCORE: f32 = 0.5;
+const MIN_CLUE_MARGIN: f32 = 0.1;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Kind {
+    Ground,
+    Chest,
+    Monster,
+    // placed by the player
+    Wall,
+    Mark,
+}
+
+impl Kind {
+    pub fn tile(self) -> Tile {
+        match self {
+            Kind::Ground => Tile::Unsure,
+            Kind::Chest => Tile::Chest,
+            Kind::Monster => Tile::Monster,
+            Kind::Wall => Tile::Wall,
+            // a mark says the player has it down as open
+            Kind::Mark => Tile::Ground,
+        }
+    }
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Kind::Ground => write!(f, "ground"),
+            Kind::Chest => write!(f, "a chest"),
+            Kind::Monster => write!(f, "a monster"),
+            Kind::Wall => write!(f, "a wall"),
+            Kind::Mark => write!(f, "a mark"),
+        }
+    }
+}
+
+// what a tile looks like and how sure that is, 1 being certain
+#[derive(Clone, Copy, Debug)]
+pub struct Read {
+    pub kind: Kind,
+    pub confidence: f32,
+}
+
+#[derive(Clone, Copy)]
+pub enum Recognition {
+    Recognized(Read),
+    // not sure enough about anything, with what it looked most like
+    Unrecognized(Read),
+}
+
+// crops from the middle of tiles to compare against
+pub struct Templates {
+    // one per tile in reading order, the floor pattern differs from tile to tile
+    pub ground: Vec<RgbaImage>,
+    pub chest: RgbaImage,
+    // any number of them, none means monsters are told apart by not looking like anything else
+    pub monsters: Vec<RgbaImage>,
+    // walls and marks the player already put down, any number of them as well, with none of
+    // a kind those can't be read either
+    pub walls: Vec<RgbaImage>,
+    pub marks: Vec<RgbaImage>,
+    // clue numbers 0 to 7
+    pub numbers: Vec<Glyph>,
+}
+
+// the ground templates of one row of tiles, left to right
+macro_rules! ground_row {
+    ($y:literal) => {
+        [
+            include_bytes!(concat!("../assets/ground/0x", $y, ".png")),
+            include_bytes!(concat!("../assets/ground/1x", $y, ".png")),
+            include_bytes!(concat!("../assets/ground/2x", $y, ".png")),
+            include_bytes!(concat!("../assets/ground/3x", $y, ".png")),
+            include_bytes!(concat!("../assets/ground/4x", $y, ".png")),
+            include_bytes!(concat!("../assets/ground/5x", $y, ".png")),
+            include_bytes!(concat!("../assets/ground/6x", $y, ".png")),
+            include_bytes!(concat!("../assets/ground/7x", $y, ".png")),
+        ]
+    };
+}
+
+// the templates in assets/ that ship, built in so it runs from anywhere
+const GROUND: [[&[u8]; 8]; 8] = [
+    ground_row!(0), ground_row!(1), ground_row!(2), ground_row!(3),
+    ground_row!(4), ground_row!(5), ground_row!(6), ground_row!(7),
+];
+const CHEST: &[u8] = include_bytes!("../assets/chest.png");
+const NUMBERS: [&[u8]; 8] = [
+    include_bytes!("../assets/numbers/0.png"),
+    include_bytes!("../assets/numbers/1.png"),
+    include_bytes!("../assets/numbers/2.png"),
+    include_bytes!("../assets/numbers/3.png"),
+    include_bytes!("../assets/numbers/4.png"),
+    include_bytes!("../assets/numbers/5.png"),
+    include_bytes!("../assets/numbers/6.png"),
+    include_bytes!("../assets/numbers/7.png"),
+];
+// MONSTERS, WALLS and MARKS, whatever build.rs found in assets/
+include!(concat!(env!("OUT_DIR"), "/templates.rs"));
+
+impl Templates {
+    // the templates built into the program
+    pub fn embedded() -> Result<Templates, String> {
+        let mut ground = vec![];
+        for (y, row) in GROUND.iter().enumerate() {
+            for (x, bytes) in row.iter().enumerate() {
+                ground.push(decode(bytes, &format!("ground/{x}x{y}.png"))?);
+            }
+        }
+        Ok(Templates {
+            ground,
+            chest: decode(CHEST, "chest.png")?,
+            monsters: decode_all(MONSTERS)?,
+            walls: decode_all(WALLS)?,
+            marks: decode_all(MARKS)?,
+            numbers: NUMBERS.iter().enumerate()
+                .map(|(number, bytes)| Ok(number_glyph(&decode(bytes, &format!("numbers/{number}.png"))?)))
+                .collect::<Result<_, String>>()?,
+        })
+    }
+
+    // {dir}/ground/{x}x{y}.png, {dir}/chest.png, {dir}/numbers/{n}.png and whatever is in
+    // {dir}/monsters/, {dir}/walls/ and {dir}/marks/
+    pub fn load(dir: &str) -> Result<Templates, String> {
+        let mut ground = vec![];
+        for y in 0..8 {
+            for x in 0..8 {
+                ground.push(load_image(&format!("{dir}/ground/{x}x{y}.png"))?);
+            }
+        }
+        Ok(Templates {
+            ground,
+            chest: load_image(&format!("{dir}/chest.png"))?,
+            monsters: load_images(&format!("{dir}/monsters"))?,
+            walls: load_images(&format!("{dir}/walls"))?,
+            marks: load_images(&format!("{dir}/marks"))?,
+            numbers: (0..8)
+                .map(|number| Ok(number_glyph(&load_image(&format!("{dir}/numbers/{number}.png"))?)))
+                .collect::<Result<_, String>>()?,
+        })
+    }
+}
+
+// the number in a template, drawn in black on white
+fn number_glyph(image: &RgbaImage) -> Glyph {
+    Glyph::new(&ink(image, &[BLACK]))
+}
+
+// every png in a directory, none if there's no such directory
+fn load_images(dir: &str) -> Result<Vec<RgbaImage>, String> {
+    let mut paths: Vec<_> = fs::read_dir(dir)
+        .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+        .unwrap_or_default();
+    paths.retain(|path| path.extension().is_some_and(|extension| extension == "png"));
+    paths.sort();
+    paths.iter().map(|path| load_image(&path.to_string_lossy())).collect()
+}
+
+fn load_image(path: &str) -> Result<RgbaImage, String> {
+    let bytes = fs::read(path).map_err(|error| format!("couldn't open {path}: {error}"))?;
+    decode(&bytes, path)
+}
+
+fn decode_all(images: &[(&str, &[u8])]) -> Result<Vec<RgbaImage>, String> {
+    images.iter().map(|(name, bytes)| decode(bytes, name)).collect()
+}
+
+fn decode(bytes: &[u8], name: &str) -> Result<RgbaImage, String> {
+    let image = image::load_from_memory(bytes).map_err(|error| format!("couldn't decode {name}: {error}"))?;
+    Ok(image.to_rgba8())
+}
+
+// the crop from the middle of a tile compared against the templates, whatever it looks most
+// like is what it is, unless it doesn't look enough like it
+//
+// without monster templates a monster is whatever doesn't look enough like anything else, like
+// it's always been. a crop of a single colour isn't one though, that's something covering the
+// board, so it still comes out unrecognized.
+pub fn classify(crop: &RgbaImage, templates: &Templates, threshold: f32) -> Recognition {
+    // kinds without templates are left out, rather than looking 0% like the crop
+    let closest = |images: &[RgbaImage]| images.iter().map(|template| similarity(crop, template)).reduce(f32::max);
+    let read = [
+        (Kind::Ground, closest(&templates.ground)),
+        (Kind::Chest, Some(similarity(crop, &templates.chest))),
+        (Kind::Monster, closest(&templates.monsters)),
+        (Kind::Wall, closest(&templates.walls)),
+        (Kind::Mark, closest(&templates.marks)),
+    ].into_iter()
+        .filter_map(|(kind, confidence)| Some(Read { kind, confidence: confidence? }))
+        .max_by(|a, b| a.confidence.total_cmp(&b.confidence))
+        .unwrap();
+    if read.confidence >= threshold {
+        Recognition::Recognized(read)
+    } else if templates.monsters.is_empty() && !flat(crop) {
+        // as sure as it is unlike the closest template
+        Recognition::Recognized(Read { kind: Kind::Monster, confidence: 1.0 - read.confidence })
+    } else {
+        Recognition::Unrecognized(read)
+    }
+}
+
+// whether every pixel is about the same colour
+fn flat(image: &RgbaImage) -> bool {
+    let Some(first) = image.pixels().next() else {
+        return true;
+    };
+    image.pixels().all(|pixel| close(pixel.0, first.0))
+}
+
+// fraction of pixels that are about the same colour in both images, 1 for identical ones
+pub fn similarity(image: &RgbaImage, template: &RgbaImage) -> f32 {
+    if image.dimensions() != template.dimensions() {
+        return 0.0;
+    }
+    let same = image.pixels().zip(template.pixels()).filter(|(a, b)| close(a.0, b.0)).count();
+    same as f32 / template.pixels().len() as f32
+}
+
+// the pixels of a number within its bounding box
+pub struct Glyph {
+    width: usize,
+    height: usize,
+    ink: Vec<bool>,
+}
+
+impl Glyph {
+    // the pixels of a number, they don't have to be in any order
+    pub fn new(inked: &[(u32, u32)]) -> Glyph {
+        let (Some(left), Some(right)) = (inked.iter().map(|p| p.0).min(), inked.iter().map(|p| p.0).max()) else {
+            return Glyph { width: 0, height: 0, ink: vec![] };
+        };
+        let top = inked.iter().map(|p| p.1).min().unwrap();
+        let bottom = inked.iter().map(|p| p.1).max().unwrap();
+        let (width, height) = ((right - left + 1) as usize, (bottom - top + 1) as usize);
+        let mut ink = vec![false; width * height];
+        for &(x, y) in inked {
+            ink[(y - top) as usize * width + (x - left) as usize] = true;
+        }
+        Glyph { width, height, ink }
+    }
+
+    fn at(&self, x: i32, y: i32) -> bool {
+        (0..self.width as i32).contains(&x) && (0..self.height as i32).contains(&y) && self.ink[y as usize * self.width + x as usize]
+    }
+
+    fn count(&self) -> usize {
+        self.ink.iter().filter(|&&ink| ink).count()
+    }
+
+    // fraction of this glyph's ink that has some of the other's within a pixel of it,
+    // with their middles lined up
+    fn covered_by(&self, other: &Glyph) -> f32 {
+        let offset_x = (other.width as i32 - self.width as i32) / 2;
+        let offset_y = (other.height as i32 - self.height as i32) / 2;
+        let mut covered = 0;
+        for y in 0..self.height as i32 {
+            for x in 0..self.width as i32 {
+                let near = |dx: i32, dy: i32| other.at(x + offset_x + dx, y + offset_y + dy);
+                if self.at(x, y) && (-1..=1).any(|dy| (-1..=1).any(|dx| near(dx, dy))) {
+                    covered += 1;
+                }
+            }
+        }
+        covered as f32 / self.count().max(1) as f32
+    }
+
+    // how alike two glyphs are, strokes a pixel thicker or thinner still count as the same
+    // but any stroke only one of them has doesn't
+    fn likeness(&self, other: &Glyph) -> f32 {
+        self.covered_by(other).min(other.covered_by(self))
+    }
+}
+
+// the number in a clue, how well it matched, and by how much it beat the next best number
+#[derive(Clone, Copy, Debug)]
+pub struct ClueRead {
+    pub number: usize,
+    pub score: f32,
+    pub margin: f32,
+    // whether the game shows the line as having the right number of walls
+    pub satisfied: bool,
+}
+
+// the number in the crop of a clue, an error rather than a guess if it doesn't clearly match one
+pub fn read_clue(crop: &RgbaImage, templates: &Templates) -> Result<ClueRead, String> {
+    let inked = ink(crop, &[ORANGE, GRAY]);
+    if inked.is_empty() {
+        return Err("has no number in it".to_string());
+    }
+    let glyph = Glyph::new(&inked);
+    let mut scores: Vec<(usize, f32)> = templates.numbers.iter().map(|number| number.likeness(&glyph)).enumerate().collect();
+    scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+    let (number, score) = scores[0];
+    let (runner_up, runner_up_score) = scores[1];
+    if score < MIN_CLUE_SCORE {
+        return Err(format!("looks most like {number} but only matches it {:.0}%", score * 100.0));
+    }
+    if score - runner_up_score < MIN_CLUE_MARGIN {
+        return Err(format!("could be {number} or {runner_up}, they match {:.0}% and {:.0}%", score * 100.0, runner_up_score * 100.0));
+    }
+    // the number is drawn all in one colour, whichever most of it is
+    let gray = inked.iter().filter(|&&(x, y)| close(crop.get_pixel(x, y).0, GRAY)).count();
+    let satisfied = gray * 2 > inked.len();
+    Ok(ClueRead { number, score, margin: score - runner_up_score, satisfied })
+}
+
+// the biggest patch of touching pixels about one of the colours, numbers are drawn in one piece
+// so a stray bit of the same colour elsewhere in the crop gets left out
+fn ink(image: &RgbaImage, colours: &[[u8; 4]]) -> Vec<(u32, u32)> {
+    let (width, height) = image.dimensions();
+    let mut unseen: Vec<bool> = image.pixels().map(|pixel| colours.iter().any(|colour| close(pixel.0, *colour))).collect();
+    let mut biggest = vec![];
+    for start in 0..unseen.len() {
+        if !unseen[start] {
+            continue;
+        }
+        unseen[start] = false;
+        let mut patch = vec![];
+        let mut queue = vec![(start as u32 % width, start as u32 / width)];
+        while let Some((x, y)) = queue.pop() {
+            patch.push((x, y));
+            for ny in y.saturating_sub(1)..(y + 2).min(height) {
+                for nx in x.saturating_sub(1)..(x + 2).min(width) {
+                    let i = (ny * width + nx) as usize;
+                    if unseen[i] {
+                        unseen[i] = false;
+                        queue.push((nx, ny));
+                    }
+                }
+            }
+        }
+        if patch.len() > biggest.len() {
+            biggest = patch;
+        }
+    }
+    biggest
+}
+
+// whether two colours count as the same, calibration goes by this too
+pub fn close(a: [u8; 4], b: [u8; 4]) -> bool {
+    let distance: u32 = (0..3).map(|i| (a[i] as i32 - b[i] as i32).pow(2) as u32).sum();
+    distance <= COLOUR_TOLERANCE * COLOUR_TOLERANCE
+}
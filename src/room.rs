@@ -0,0 +1,184 @@
+use crate::{find_contradiction, Tile};
+
+// a 3x3 treasure room, left and top being its top left ground tile
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Room {
+    pub left: usize,
+    pub top: usize,
+}
+
+impl Room {
+    pub fn inside(&self) -> impl Iterator<Item = (usize, usize)> {
+        let Room { left, top } = *self;
+        (top..top + 3).flat_map(move |y| (left..left + 3).map(move |x| (x, y)))
+    }
+
+    // tiles around the room that could be its exit, corners can't be
+    // and anything past the edge of the board is wall already
+    pub fn side_walls(&self) -> Vec<(usize, usize)> {
+        let Room { left, top } = *self;
+        let mut side_walls = vec![];
+        for i in 0..3 {
+            if top > 0 {
+                side_walls.push((left + i, top - 1));
+            }
+            if top + 3 < 8 {
+                side_walls.push((left + i, top + 3));
+            }
+            if left > 0 {
+                side_walls.push((left - 1, top + i));
+            }
+            if left + 3 < 8 {
+                side_walls.push((left + 3, top + i));
+            }
+        }
+        side_walls
+    }
+
+    // side walls that aren't walls, the edge of the board never is one
+    pub fn possible_exits(&self, matrix: &[Vec<Tile>]) -> Vec<(usize, usize)> {
+        self.side_walls().into_iter().filter(|&(x, y)| matrix[y][x] != Tile::Wall).collect()
+    }
+
+    // the matrix with this room built into it, walled off except for the exit
+    pub fn build(&self, matrix: &[Vec<Tile>], exit: (usize, usize)) -> Vec<Vec<Tile>> {
+        let mut matrix = matrix.to_vec();
+        for (x, y) in self.inside() {
+            if matrix[y][x] == Tile::Unsure {
+                matrix[y][x] = Tile::Ground;
+            }
+        }
+        for (x, y) in self.side_walls() {
+            if matrix[y][x] == Tile::Unsure {
+                matrix[y][x] = if (x, y) == exit { Tile::Ground } else { Tile::Wall };
+            }
+        }
+        matrix
+    }
+}
+
+// rooms around the chest at x, y the matrix doesn't rule out yet
+pub fn placements(matrix: &[Vec<Tile>], x: usize, y: usize) -> Vec<Room> {
+    let mut rooms = vec![];
+    for top in y.saturating_sub(2)..=y.min(5) {
+        for left in x.saturating_sub(2)..=x.min(5) {
+            let room = Room { left, top };
+            let inside_open = room.inside().all(|(x, y)| !matches!(matrix[y][x], Tile::Monster | Tile::Wall));
+            let side_walls = room.side_walls();
+            let side_walls_closed = side_walls.iter().all(|&(x, y)| !matches!(matrix[y][x], Tile::Monster | Tile::Chest));
+            let openings = side_walls.iter().filter(|&&(x, y)| matrix[y][x] == Tile::Ground).count();
+            if inside_open && side_walls_closed && openings <= 1 {
+                rooms.push(room);
+            }
+        }
+    }
+    rooms
+}
+
+// side wall tiles that can still be the room's exit
+//
+// the room gets built with each one as its exit in turn, whatever breaks a rule
+// (a corridor leading nowhere, a monster getting walled in, too many walls for a clue)
+// can't be it. an exit that's already open is the only candidate.
+pub fn viable_exits(matrix: &[Vec<Tile>], room: Room, nums_columns: &[usize], nums_rows: &[usize]) -> Vec<(usize, usize)> {
+    let side_walls = room.side_walls();
+    let open: Vec<(usize, usize)> = side_walls.iter().copied().filter(|&(x, y)| matrix[y][x] == Tile::Ground).collect();
+    let candidates = if open.is_empty() {
+        side_walls.into_iter().filter(|&(x, y)| matrix[y][x] == Tile::Unsure).collect()
+    } else {
+        open
+    };
+    candidates.into_iter()
+        .filter(|&exit| find_contradiction(&room.build(matrix, exit), nums_columns, nums_rows).is_none())
+        .collect()
+}
+
+// whether the room and its side walls are all collapsed already
+pub fn is_built(matrix: &[Vec<Tile>], room: Room) -> bool {
+    room.inside().chain(room.side_walls()).all(|(x, y)| matrix[y][x] != Tile::Unsure)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{contradiction::Contradiction, has_treasure_room};
+
+    const CORNERS: [Room; 4] = [
+        Room { left: 0, top: 0 },
+        Room { left: 5, top: 0 },
+        Room { left: 0, top: 5 },
+        Room { left: 5, top: 5 },
+    ];
+    const EDGES: [Room; 4] = [
+        Room { left: 2, top: 0 },
+        Room { left: 5, top: 2 },
+        Room { left: 2, top: 5 },
+        Room { left: 0, top: 2 },
+    ];
+
+    // the room with a chest in its middle, built with its first side wall as the exit
+    // and everything else walled up
+    fn walled_in(room: Room) -> (Vec<Vec<Tile>>, (usize, usize)) {
+        let mut matrix = vec![vec![Tile::Unsure; 8]; 8];
+        matrix[room.top + 1][room.left + 1] = Tile::Chest;
+        let mut matrix = room.build(&matrix, room.side_walls()[0]);
+        for tile in matrix.iter_mut().flatten() {
+            if *tile == Tile::Unsure {
+                *tile = Tile::Wall;
+            }
+        }
+        (matrix, (room.left + 1, room.top + 1))
+    }
+
+    fn check_room(room: Room, side_walls: usize) {
+        let (matrix, (x, y)) = walled_in(room);
+        assert_eq!(room.side_walls().len(), side_walls, "{room:?}");
+        assert!(room.side_walls().iter().all(|&(x, y)| x < 8 && y < 8), "{room:?}");
+        assert!(placements(&matrix, x, y).contains(&room), "{room:?}");
+        assert_eq!(room.possible_exits(&matrix), vec![room.side_walls()[0]], "{room:?}");
+        assert!(has_treasure_room(&matrix, x, y), "{room:?}");
+        assert!(!matches!(
+            find_contradiction(&matrix, &[usize::MAX; 8], &[usize::MAX; 8]),
+            Some(Contradiction::OpenArea { .. } | Contradiction::NoTreasureRoom { .. })
+        ), "{room:?}");
+
+        // the edge of the board doesn't count as an exit
+        let mut closed = matrix.clone();
+        let (ex, ey) = room.side_walls()[0];
+        closed[ey][ex] = Tile::Wall;
+        assert!(!has_treasure_room(&closed, x, y), "{room:?}");
+        assert_eq!(
+            find_contradiction(&closed, &[usize::MAX; 8], &[usize::MAX; 8]),
+            Some(Contradiction::NoTreasureRoom { x, y }),
+            "{room:?}"
+        );
+    }
+
+    #[test]
+    fn rooms_in_corners() {
+        for room in CORNERS {
+            check_room(room, 6);
+        }
+    }
+
+    #[test]
+    fn rooms_along_edges() {
+        for room in EDGES {
+            check_room(room, 9);
+        }
+    }
+
+    #[test]
+    fn placements_stay_on_the_board() {
+        for y in 0..8 {
+            for x in 0..8 {
+                let mut matrix = vec![vec![Tile::Unsure; 8]; 8];
+                matrix[y][x] = Tile::Chest;
+                let rooms = placements(&matrix, x, y);
+                let across = |i: usize| 3 - i.saturating_sub(5) - 2usize.saturating_sub(i);
+                assert_eq!(rooms.len(), across(x) * across(y), "chest at {x}, {y}");
+                assert!(rooms.iter().all(|room| room.left + 3 <= 8 && room.top + 3 <= 8));
+            }
+        }
+    }
+}
@@ -0,0 +1,506 @@
+use std::sync::{Arc, LazyLock};
+
+use crate::{room, Tile};
+
+const DIRECTIONS: [(i32, i32); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+
+// the tiles being worked out
+pub type Board = [Vec<Tile>];
+
+// the clues a board has to match
+pub struct Puzzle<'a> {
+    pub nums_columns: &'a [usize],
+    pub nums_rows: &'a [usize],
+}
+
+// a tile a rule collapsed
+#[derive(PartialEq, Eq, Clone)]
+pub struct Change {
+    pub x: usize,
+    pub y: usize,
+    // what it was before the rule got to it
+    pub before: Tile,
+    pub tile: Tile,
+}
+
+// one kind of certainty collapse
+pub trait DeductionRule: Send + Sync {
+    // short name to pick the rule by, e.g. on the command line
+    fn name(&self) -> &'static str;
+
+    // collapses everything this rule is sure about, returns what it changed
+    fn apply(&self, board: &mut Board, puzzle: &Puzzle) -> Vec<Change>;
+}
+
+// rules applied one after another, in order
+#[derive(Clone)]
+pub struct RuleSet {
+    rules: Vec<Arc<dyn DeductionRule>>,
+}
+
+static STANDARD: LazyLock<RuleSet> = LazyLock::new(|| RuleSet {
+    rules: vec![
+        Arc::new(Rows),
+        Arc::new(Columns),
+        Arc::new(MonsterEscape),
+        Arc::new(MonsterEnclosure),
+        Arc::new(ChestTwoGround),
+        Arc::new(ChestGap),
+        Arc::new(GroundContinues),
+        Arc::new(RoomExit),
+    ],
+});
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        STANDARD.clone()
+    }
+}
+
+impl RuleSet {
+    // every rule the solver knows, in the order it runs them
+    pub fn standard() -> &'static RuleSet {
+        &STANDARD
+    }
+
+    pub fn empty() -> Self {
+        RuleSet { rules: vec![] }
+    }
+
+    pub fn push(&mut self, rule: Arc<dyn DeductionRule>) {
+        self.rules.push(rule);
+    }
+
+    // adds a rule to run before all the ones at index and after it
+    pub fn insert(&mut self, index: usize, rule: Arc<dyn DeductionRule>) {
+        self.rules.insert(index, rule);
+    }
+
+    // takes the rule out, none if there's no rule by that name
+    pub fn remove(&mut self, name: &str) -> Option<Arc<dyn DeductionRule>> {
+        let index = self.position(name)?;
+        Some(self.rules.remove(index))
+    }
+
+    pub fn position(&self, name: &str) -> Option<usize> {
+        self.rules.iter().position(|rule| rule.name() == name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Arc<dyn DeductionRule>> {
+        self.rules.iter().find(|rule| rule.name() == name)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.rules.iter().map(|rule| rule.name())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<dyn DeductionRule>> {
+        self.rules.iter()
+    }
+
+    // a single round of every rule
+    pub fn apply(&self, board: &mut Board, puzzle: &Puzzle) -> Vec<Change> {
+        let mut changes = vec![];
+        for rule in self.rules.iter() {
+            changes.extend(rule.apply(board, puzzle));
+        }
+        changes
+    }
+}
+
+// collapse resolved rows
+pub struct Rows;
+
+impl DeductionRule for Rows {
+    fn name(&self) -> &'static str {
+        "rows"
+    }
+
+    fn apply(&self, board: &mut Board, puzzle: &Puzzle) -> Vec<Change> {
+        let mut changes = vec![];
+        for y in 0..8 {
+            let unsure_count = board[y].iter().filter(|item| item == &&Tile::Unsure).count();
+            let wall_count = board[y].iter().filter(|item| item == &&Tile::Wall).count();
+            if wall_count + unsure_count == puzzle.nums_rows[y] {
+                fill_unsure((0..8).map(|x| (x, y)), board, Tile::Wall, &mut changes);
+            }
+            if wall_count == puzzle.nums_rows[y] && unsure_count > 0 {
+                fill_unsure((0..8).map(|x| (x, y)), board, Tile::Ground, &mut changes);
+            }
+        }
+        changes
+    }
+}
+
+// collapse resolved columns
+pub struct Columns;
+
+impl DeductionRule for Columns {
+    fn name(&self) -> &'static str {
+        "columns"
+    }
+
+    fn apply(&self, board: &mut Board, puzzle: &Puzzle) -> Vec<Change> {
+        let mut changes = vec![];
+        for x in 0..8 {
+            let unsure_count = board.iter().filter(|row| row[x] == Tile::Unsure).count();
+            let wall_count = board.iter().filter(|row| row[x] == Tile::Wall).count();
+            if wall_count + unsure_count == puzzle.nums_columns[x] {
+                fill_unsure((0..8).map(|y| (x, y)), board, Tile::Wall, &mut changes);
+            }
+            if wall_count == puzzle.nums_columns[x] && unsure_count > 0 {
+                fill_unsure((0..8).map(|y| (x, y)), board, Tile::Ground, &mut changes);
+            }
+        }
+        changes
+    }
+}
+
+// collapse monster escape routes, a monster walled in on three sides leaves through the fourth
+pub struct MonsterEscape;
+
+impl DeductionRule for MonsterEscape {
+    fn name(&self) -> &'static str {
+        "monster-escape"
+    }
+
+    fn apply(&self, board: &mut Board, _puzzle: &Puzzle) -> Vec<Change> {
+        let mut changes = vec![];
+        for y in 0..8 {
+            for x in 0..8 {
+                if board[y][x] == Tile::Monster {
+                    // the edge of the board counts as wall
+                    let walls = DIRECTIONS.iter()
+                        .filter(|&&(dx, dy)| tile_at(board, x, y, dx, dy).is_none_or(|tile| tile == &Tile::Wall))
+                        .count();
+                    if walls == 3 {
+                        for (nx, ny) in neighbours(board, x, y) {
+                            if board[ny][nx] != Tile::Wall {
+                                set(board, nx, ny, Tile::Ground, &mut changes);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        changes
+    }
+}
+
+// collapse monster wall enclosures, once a monster has its exit everything else around it is wall
+pub struct MonsterEnclosure;
+
+impl DeductionRule for MonsterEnclosure {
+    fn name(&self) -> &'static str {
+        "monster-enclosure"
+    }
+
+    fn apply(&self, board: &mut Board, _puzzle: &Puzzle) -> Vec<Change> {
+        let mut changes = vec![];
+        for y in 0..8 {
+            for x in 0..8 {
+                if board[y][x] == Tile::Monster {
+                    let escape_route = neighbours(board, x, y).into_iter().any(|(nx, ny)| board[ny][nx] == Tile::Ground);
+                    if escape_route {
+                        for (nx, ny) in neighbours(board, x, y) {
+                            if board[ny][nx] != Tile::Ground {
+                                set(board, nx, ny, Tile::Wall, &mut changes);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        changes
+    }
+}
+
+// rows and cols with a chest always have at least 2 ground tiles,
+// so a wall right next to the chest means the two tiles on the other side are ground
+pub struct ChestTwoGround;
+
+impl DeductionRule for ChestTwoGround {
+    fn name(&self) -> &'static str {
+        "chest-two-ground"
+    }
+
+    fn apply(&self, board: &mut Board, _puzzle: &Puzzle) -> Vec<Change> {
+        let mut changes = vec![];
+        for y in 0..8 {
+            for x in 0..8 {
+                if board[y][x] == Tile::Chest {
+                    for (dx, dy) in DIRECTIONS {
+                        if tile_at(board, x, y, dx, dy).is_none_or(|tile| tile == &Tile::Wall) {
+                            let across = [offset(board, x, y, -dx, -dy), offset(board, x, y, -dx * 2, -dy * 2)];
+                            for (mx, my) in across.into_iter().flatten() {
+                                set(board, mx, my, Tile::Ground, &mut changes);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        changes
+    }
+}
+
+// same as ChestTwoGround, for a wall gapped from the chest by one tile
+pub struct ChestGap;
+
+impl DeductionRule for ChestGap {
+    fn name(&self) -> &'static str {
+        "chest-gap"
+    }
+
+    fn apply(&self, board: &mut Board, _puzzle: &Puzzle) -> Vec<Change> {
+        let mut changes = vec![];
+        for y in 0..8 {
+            for x in 0..8 {
+                if board[y][x] == Tile::Chest {
+                    for (dx, dy) in DIRECTIONS {
+                        if tile_at(board, x, y, dx * 2, dy * 2).is_none_or(|tile| tile == &Tile::Wall) {
+                            if let Some((mx, my)) = offset(board, x, y, -dx, -dy) {
+                                set(board, mx, my, Tile::Ground, &mut changes);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        changes
+    }
+}
+
+// let's set some ground rules
+// ground with two walls or three monsters around it can't be a dead end, so it carries on into the unsure tiles
+pub struct GroundContinues;
+
+impl DeductionRule for GroundContinues {
+    fn name(&self) -> &'static str {
+        "ground-continues"
+    }
+
+    fn apply(&self, board: &mut Board, _puzzle: &Puzzle) -> Vec<Change> {
+        let mut changes = vec![];
+        for y in 0..8 {
+            for x in 0..8 {
+                if board[y][x] == Tile::Ground {
+                    let mut walls = 0;
+                    let mut monsters = 0;
+                    let mut unsures = 0;
+                    for (dx, dy) in DIRECTIONS {
+                        match tile_at(board, x, y, dx, dy) {
+                            Some(Tile::Unsure) => unsures += 1,
+                            Some(Tile::Monster) => monsters += 1,
+                            Some(Tile::Wall) | None => walls += 1,
+                            Some(Tile::Chest | Tile::Ground) => (),
+                        }
+                    }
+                    if unsures != 0 && (monsters == 3 || walls == 2) {
+                        // replace unsure with ground
+                        fill_unsure(neighbours(board, x, y), board, Tile::Ground, &mut changes);
+                    }
+                }
+            }
+        }
+        changes
+    }
+}
+
+// once a chest is down to a single room that can go around it, and that room to a single exit
+// that doesn't break a rule, the room gets built. rooms without any exit left don't count.
+pub struct RoomExit;
+
+impl DeductionRule for RoomExit {
+    fn name(&self) -> &'static str {
+        "room-exit"
+    }
+
+    fn apply(&self, board: &mut Board, puzzle: &Puzzle) -> Vec<Change> {
+        let mut changes = vec![];
+        for y in 0..8 {
+            for x in 0..8 {
+                if board[y][x] != Tile::Chest {
+                    continue;
+                }
+                let rooms = room::placements(board, x, y);
+                if rooms.iter().any(|&room| room::is_built(board, room)) {
+                    continue;
+                }
+                // no need to look any further once two rooms still work
+                let viable: Vec<_> = rooms.into_iter()
+                    .map(|room| (room, room::viable_exits(board, room, puzzle.nums_columns, puzzle.nums_rows)))
+                    .filter(|(_, exits)| !exits.is_empty())
+                    .take(2)
+                    .collect();
+                if let [(room, exits)] = viable.as_slice() {
+                    if let [exit] = exits.as_slice() {
+                        let built = room.build(board, *exit);
+                        for (x, y) in room.inside().chain(room.side_walls()) {
+                            set(board, x, y, built[y][x].clone(), &mut changes);
+                        }
+                    }
+                }
+            }
+        }
+        changes
+    }
+}
+
+// coordinates at an offset from x, y, none past the edge of the board
+// the chest rules can reach that far on a board that's already impossible
+fn offset(board: &Board, x: usize, y: usize, dx: i32, dy: i32) -> Option<(usize, usize)> {
+    let nx = x.checked_add_signed(dx as isize)?;
+    let ny = y.checked_add_signed(dy as isize)?;
+    board.get(ny)?.get(nx)?;
+    Some((nx, ny))
+}
+
+// tile at an offset from x, y, none past the edge of the board
+fn tile_at(board: &Board, x: usize, y: usize, dx: i32, dy: i32) -> Option<&Tile> {
+    let (nx, ny) = offset(board, x, y, dx, dy)?;
+    Some(&board[ny][nx])
+}
+
+// coordinates of the tiles next to x, y that are on the board
+fn neighbours(board: &Board, x: usize, y: usize) -> Vec<(usize, usize)> {
+    DIRECTIONS.iter().filter_map(|&(dx, dy)| offset(board, x, y, dx, dy)).collect()
+}
+
+// sets a tile, noting it down if that changed anything
+fn set(board: &mut Board, x: usize, y: usize, tile: Tile, changes: &mut Vec<Change>) {
+    if board[y][x] != tile {
+        let before = board[y][x].clone();
+        board[y][x] = tile.clone();
+        changes.push(Change { x, y, before, tile });
+    }
+}
+
+fn fill_unsure(cells: impl IntoIterator<Item = (usize, usize)>, board: &mut Board, tile: Tile, changes: &mut Vec<Change>) {
+    for (x, y) in cells {
+        if board[y][x] == Tile::Unsure {
+            set(board, x, y, tile.clone(), changes);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ANY_COUNT;
+
+    fn board(rows: [&str; 8]) -> Vec<Vec<Tile>> {
+        rows.iter().map(|row| row.chars().map(|symbol| Tile::from_symbol(symbol).unwrap()).collect()).collect()
+    }
+
+    fn symbols(board: &Board) -> Vec<String> {
+        board.iter().map(|row| row.iter().map(Tile::symbol).collect()).collect()
+    }
+
+    // the board after a single round of the rule, with clues that fit any number of walls
+    fn apply(rule: &dyn DeductionRule, rows: [&str; 8]) -> Vec<String> {
+        apply_with_clues(rule, rows, &[ANY_COUNT; 8], &[ANY_COUNT; 8])
+    }
+
+    fn apply_with_clues(rule: &dyn DeductionRule, rows: [&str; 8], nums_columns: &[usize], nums_rows: &[usize]) -> Vec<String> {
+        let before = board(rows);
+        let mut after = before.clone();
+        let changes = rule.apply(&mut after, &Puzzle { nums_columns, nums_rows });
+        for change in changes.iter() {
+            assert!(before[change.y][change.x] == change.before && after[change.y][change.x] == change.tile);
+        }
+        symbols(&after)
+    }
+
+    #[test]
+    fn rows() {
+        let rows = ["#??_____", "#??_____", "________", "________", "________", "________", "________", "________"];
+        assert_eq!(
+            apply_with_clues(&Rows, rows, &[ANY_COUNT; 8], &[3, 1, 0, 0, 0, 0, 0, 0]),
+            ["###_____", "#_______", "________", "________", "________", "________", "________", "________"],
+        );
+    }
+
+    #[test]
+    fn columns() {
+        let rows = ["##______", "??______", "??______", "________", "________", "________", "________", "________"];
+        assert_eq!(
+            apply_with_clues(&Columns, rows, &[3, 1, 0, 0, 0, 0, 0, 0], &[ANY_COUNT; 8]),
+            ["##______", "#_______", "#_______", "________", "________", "________", "________", "________"],
+        );
+    }
+
+    #[test]
+    fn monster_escape() {
+        // the corner monster has the edge on two sides, the other one only two walls
+        let rows = ["!#??????", "????????", "????????", "????????", "????????", "????#!#?", "????????", "????????"];
+        assert_eq!(
+            apply(&MonsterEscape, rows),
+            ["!#??????", "_???????", "????????", "????????", "????????", "????#!#?", "????????", "????????"],
+        );
+    }
+
+    #[test]
+    fn monster_enclosure() {
+        let rows = ["????????", "????????", "???_????", "???!????", "????????", "????????", "?????!??", "????????"];
+        assert_eq!(
+            apply(&MonsterEnclosure, rows),
+            ["????????", "????????", "???_????", "??#!#???", "???#????", "????????", "?????!??", "????????"],
+        );
+    }
+
+    #[test]
+    fn chest_two_ground() {
+        let rows = ["????????", "????????", "????????", "??#O????", "????????", "????????", "????????", "????????"];
+        assert_eq!(
+            apply(&ChestTwoGround, rows),
+            ["????????", "????????", "????????", "??#O__??", "????????", "????????", "????????", "????????"],
+        );
+    }
+
+    #[test]
+    fn chest_gap() {
+        let rows = ["????????", "???#????", "????????", "???O????", "????????", "????????", "????????", "????????"];
+        assert_eq!(
+            apply(&ChestGap, rows),
+            ["????????", "???#????", "????????", "???O????", "???_????", "????????", "????????", "????????"],
+        );
+    }
+
+    #[test]
+    fn ground_continues() {
+        // the edge counts as wall in the corner, ground further in has nothing forcing it
+        let rows = ["_???????", "????????", "????????", "????_???", "????????", "????????", "????????", "????????"];
+        assert_eq!(
+            apply(&GroundContinues, rows),
+            ["__??????", "_???????", "????????", "????_???", "????????", "????????", "????????", "????????"],
+        );
+    }
+
+    #[test]
+    fn room_exit() {
+        // walls rule out every room around the chest but the one in the corner, which has one way out left
+        let rows = ["???#????", "?O?#????", "????????", "###?????", "????????", "????????", "????????", "????????"];
+        assert_eq!(
+            apply(&RoomExit, rows),
+            ["___#????", "_O_#????", "____????", "###?????", "????????", "????????", "????????", "????????"],
+        );
+    }
+
+    #[test]
+    fn disabled_rule() {
+        let rows = ["_???????", "????????", "????????", "????????", "????????", "????????", "????????", "????????"];
+        let puzzle = Puzzle { nums_columns: &[ANY_COUNT; 8], nums_rows: &[ANY_COUNT; 8] };
+
+        let mut with = board(rows);
+        assert_eq!(RuleSet::default().apply(&mut with, &puzzle).len(), 2);
+
+        let mut rules = RuleSet::default();
+        assert!(rules.remove("ground-continues").is_some());
+        assert!(rules.remove("ground-continues").is_none());
+        assert!(!rules.names().any(|name| name == "ground-continues"));
+        let mut without = board(rows);
+        assert!(rules.apply(&mut without, &puzzle).is_empty());
+        assert_eq!(symbols(&without), rows);
+    }
+}
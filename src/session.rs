@@ -0,0 +1,212 @@
+use std::fmt;
+
+use crate::{check_size, contradiction::{Contradiction, Position}, find_contradiction, options::SolveOptions, settle, Tile};
+
+// why a move got refused
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum MoveError {
+    OutOfBounds { x: usize, y: usize },
+    // monsters and chests are part of the puzzle, they can't be marked over
+    FixedTile { x: usize, y: usize },
+    // only walls and ground can be placed
+    NotAMark,
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MoveError::OutOfBounds { x, y } => write!(f, "{} is outside the board", Position(*x, *y)),
+            MoveError::FixedTile { x, y } => write!(f, "{} holds a monster or a chest", Position(*x, *y)),
+            MoveError::NotAMark => write!(f, "only walls and ground can be placed"),
+        }
+    }
+}
+
+// what a move led to
+pub enum Update {
+    // cells the marks force now that they didn't before, with what they have to be
+    Forced(Vec<((usize, usize), Tile)>),
+    // the marks broke this rule, so they can't lead to a solution anymore
+    Broken(Contradiction),
+}
+
+// a puzzle being solved by hand, one mark at a time
+//
+// every change re-runs the certainty collapses from the marks alone, so taking a mark
+// back also takes back everything it forced.
+pub struct Session {
+    nums_columns: Vec<usize>,
+    nums_rows: Vec<usize>,
+    // the puzzle with the player's walls and ground on it
+    marks: Vec<Vec<Tile>>,
+    // marks with everything they force filled in
+    board: Vec<Vec<Tile>>,
+    conflict: Option<Contradiction>,
+    // cell and what it held before each change, newest last
+    history: Vec<((usize, usize), Tile)>,
+}
+
+impl Session {
+    // walls and ground already in the matrix count as the player's marks
+    pub fn new(matrix: Vec<Vec<Tile>>, nums_columns: Vec<usize>, nums_rows: Vec<usize>) -> Result<Self, String> {
+        check_size(&matrix, &nums_columns, &nums_rows)?;
+        let mut session = Session {
+            nums_columns,
+            nums_rows,
+            board: matrix.clone(),
+            marks: matrix,
+            conflict: None,
+            history: vec![],
+        };
+        session.propagate(vec![]);
+        Ok(session)
+    }
+
+    // places a wall or ground, unsure clears the cell instead
+    pub fn set(&mut self, cell: (usize, usize), tile: Tile) -> Result<Update, MoveError> {
+        let (x, y) = cell;
+        let Some(current) = self.marks.get(y).and_then(|row| row.get(x)) else {
+            return Err(MoveError::OutOfBounds { x, y });
+        };
+        if matches!(current, Tile::Monster | Tile::Chest) {
+            return Err(MoveError::FixedTile { x, y });
+        }
+        if matches!(tile, Tile::Monster | Tile::Chest) {
+            return Err(MoveError::NotAMark);
+        }
+        let forced_before = self.forced_cells();
+        if current != &tile {
+            self.history.push((cell, current.clone()));
+            self.marks[y][x] = tile;
+        }
+        Ok(self.propagate(forced_before))
+    }
+
+    pub fn clear(&mut self, cell: (usize, usize)) -> Result<Update, MoveError> {
+        self.set(cell, Tile::Unsure)
+    }
+
+    // takes back the last change, none if there's nothing left to take back
+    pub fn undo(&mut self) -> Option<Update> {
+        let ((x, y), tile) = self.history.pop()?;
+        let forced_before = self.forced_cells();
+        self.marks[y][x] = tile;
+        Some(self.propagate(forced_before))
+    }
+
+    // cells the marks force that haven't been marked themselves
+    pub fn forced_cells(&self) -> Vec<((usize, usize), Tile)> {
+        let mut forced = vec![];
+        for y in 0..self.board.len() {
+            for x in 0..self.board[y].len() {
+                if self.marks[y][x] == Tile::Unsure && self.board[y][x] != Tile::Unsure {
+                    forced.push(((x, y), self.board[y][x].clone()));
+                }
+            }
+        }
+        forced
+    }
+
+    // the rule the current marks break, if any
+    pub fn conflicts(&self) -> Option<Contradiction> {
+        self.conflict
+    }
+
+    pub fn marks(&self) -> &[Vec<Tile>] {
+        &self.marks
+    }
+
+    // marks and forced cells together
+    pub fn board(&self) -> &[Vec<Tile>] {
+        &self.board
+    }
+
+    fn propagate(&mut self, forced_before: Vec<((usize, usize), Tile)>) -> Update {
+        let mut board = self.marks.clone();
+        if settle(&mut board, &self.nums_columns, &self.nums_rows, &SolveOptions::default()) {
+            self.board = board;
+            self.conflict = None;
+            let newly_forced = self.forced_cells().into_iter().filter(|forced| !forced_before.contains(forced)).collect();
+            Update::Forced(newly_forced)
+        } else {
+            // nothing counts as forced by marks that are already broken
+            self.board = self.marks.clone();
+            let contradiction = find_contradiction(&board, &self.nums_columns, &self.nums_rows).unwrap();
+            self.conflict = Some(contradiction);
+            Update::Broken(contradiction)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_board;
+
+    const PUZZLE: &str = "
+         23243335
+        1???????!
+        4?O??????
+        0???????!
+        6????????
+        1!???????
+        6????????
+        2!??????!
+        5????????
+    ";
+
+    fn session() -> Session {
+        let (matrix, nums_columns, nums_rows) = parse_board(PUZZLE).unwrap();
+        Session::new(matrix, nums_columns, nums_rows).unwrap()
+    }
+
+    #[test]
+    fn placing_a_mark_forces_cells() {
+        let mut session = session();
+        let Ok(Update::Forced(forced)) = session.set((4, 3), Tile::Ground) else {
+            panic!("ground at column 5, row 4 is part of the solution");
+        };
+        assert_eq!(forced.len(), 9);
+        assert!(forced.contains(&((3, 3), Tile::Wall)));
+        assert!(forced.contains(&((6, 3), Tile::Ground)));
+        assert!(forced.iter().all(|forced| session.forced_cells().contains(forced)));
+        assert!(session.marks()[3][4] == Tile::Ground);
+        assert_eq!(session.conflicts(), None);
+    }
+
+    #[test]
+    fn undo_takes_back_what_a_broken_mark_did() {
+        let mut session = session();
+        let board = session.board().to_vec();
+        let contradiction = Contradiction::OpenArea { x: 5, y: 4 };
+        assert!(matches!(session.set((3, 3), Tile::Ground), Ok(Update::Broken(broken)) if broken == contradiction));
+        assert_eq!(session.conflicts(), Some(contradiction));
+        assert!(session.forced_cells().is_empty());
+
+        assert!(matches!(session.undo(), Some(Update::Forced(_))));
+        assert_eq!(session.conflicts(), None);
+        assert!(session.marks()[3][3] == Tile::Unsure);
+        assert!(session.board() == board.as_slice());
+        assert!(session.undo().is_none());
+    }
+
+    #[test]
+    fn refused_moves() {
+        let mut session = session();
+        assert_eq!(session.set((8, 0), Tile::Wall).err(), Some(MoveError::OutOfBounds { x: 8, y: 0 }));
+        assert_eq!(session.set((7, 0), Tile::Wall).err(), Some(MoveError::FixedTile { x: 7, y: 0 }));
+        assert_eq!(session.set((0, 0), Tile::Chest).err(), Some(MoveError::NotAMark));
+        assert!(session.undo().is_none());
+    }
+
+    #[test]
+    fn boards_that_arent_8x8() {
+        let (matrix, nums_columns, nums_rows) = parse_board(PUZZLE).unwrap();
+        assert!(Session::new(matrix.clone(), nums_columns[..7].to_vec(), nums_rows.clone()).is_err());
+        assert!(Session::new(matrix.clone(), nums_columns.clone(), vec![1; 9]).is_err());
+        assert!(Session::new(matrix[..7].to_vec(), nums_columns.clone(), nums_rows.clone()).is_err());
+        let mut short_row = matrix;
+        short_row[2].pop();
+        assert!(Session::new(short_row, nums_columns, nums_rows).is_err());
+    }
+}
@@ -0,0 +1,85 @@
+use std::{fmt, sync::atomic::{AtomicU64, AtomicUsize, Ordering}, time::{Duration, Instant}};
+
+use crate::contradiction::Contradiction;
+
+// how hard a solve turned out to be
+#[derive(Clone, Default, Debug)]
+pub struct SolveStats {
+    // matrices the search branched from
+    pub nodes: u64,
+    // collapses skipped because the same matrix had been seen before
+    pub duplicates: u64,
+    // the ones among them it was already proven to lead nowhere
+    pub known_impossible: u64,
+    // collapses thrown out, by the rule they broke, see Contradiction::CATEGORIES
+    pub contradictions: [u64; Contradiction::CATEGORIES.len()],
+    pub propagation_rounds: u64,
+    pub max_depth: usize,
+    pub branching_time: Duration,
+    pub checking_time: Duration,
+    pub propagation_time: Duration,
+    pub total_time: Duration,
+}
+
+impl fmt::Display for SolveStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "nodes expanded      {}", self.nodes)?;
+        writeln!(f, "duplicates skipped  {}", self.duplicates)?;
+        writeln!(f, "  known impossible  {}", self.known_impossible)?;
+        writeln!(f, "propagation rounds  {}", self.propagation_rounds)?;
+        writeln!(f, "max depth           {}", self.max_depth)?;
+        writeln!(f, "contradictions")?;
+        for (category, count) in Contradiction::CATEGORIES.iter().zip(self.contradictions) {
+            writeln!(f, "  {category:<24} {count}")?;
+        }
+        writeln!(f, "time")?;
+        writeln!(f, "  branching   {:>10.3?}", self.branching_time)?;
+        writeln!(f, "  checking    {:>10.3?}", self.checking_time)?;
+        writeln!(f, "  propagation {:>10.3?}", self.propagation_time)?;
+        writeln!(f, "  total       {:>10.3?}", self.total_time)
+    }
+}
+
+// SolveStats as it's being counted, by however many threads
+#[derive(Default)]
+pub struct StatsCounters {
+    pub nodes: AtomicU64,
+    pub duplicates: AtomicU64,
+    pub known_impossible: AtomicU64,
+    pub contradictions: [AtomicU64; Contradiction::CATEGORIES.len()],
+    pub propagation_rounds: AtomicU64,
+    pub max_depth: AtomicUsize,
+    pub branching_nanos: AtomicU64,
+    pub checking_nanos: AtomicU64,
+    pub propagation_nanos: AtomicU64,
+}
+
+impl StatsCounters {
+    pub fn count(counter: &AtomicU64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    // runs f, adding the time it took to the counter
+    pub fn time<T>(nanos: &AtomicU64, f: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = f();
+        nanos.fetch_add(start.elapsed().as_nanos() as u64, Ordering::Relaxed);
+        result
+    }
+
+    pub fn snapshot(&self, total_time: Duration) -> SolveStats {
+        let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+        SolveStats {
+            nodes: load(&self.nodes),
+            duplicates: load(&self.duplicates),
+            known_impossible: load(&self.known_impossible),
+            contradictions: self.contradictions.each_ref().map(load),
+            propagation_rounds: load(&self.propagation_rounds),
+            max_depth: self.max_depth.load(Ordering::Relaxed),
+            branching_time: Duration::from_nanos(load(&self.branching_nanos)),
+            checking_time: Duration::from_nanos(load(&self.checking_nanos)),
+            propagation_time: Duration::from_nanos(load(&self.propagation_nanos)),
+            total_time,
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// 2^18 buckets of two slots, 8MB no matter how long the search runs
+const BUCKET_BITS: u32 = 18;
+
+const OCCUPIED: u64 = 1 << 8;
+const IMPOSSIBLE: u64 = 1 << 9;
+
+// the xor of key and data is stored next to the data, so a slot torn by two
+// threads writing at once fails the check instead of handing out a wrong entry
+#[derive(Default)]
+struct Slot {
+    check: AtomicU64,
+    data: AtomicU64,
+}
+
+impl Slot {
+    fn load(&self, hash: u64) -> Option<u64> {
+        let data = self.data.load(Ordering::Relaxed);
+        let check = self.check.load(Ordering::Relaxed);
+        (data & OCCUPIED != 0 && check ^ data == hash).then_some(data)
+    }
+
+    fn save(&self, hash: u64, data: u64) {
+        self.data.store(data, Ordering::Relaxed);
+        self.check.store(hash ^ data, Ordering::Relaxed);
+    }
+}
+
+// fixed size table of zobrist hashed matrices the search has already been through
+//
+// every bucket has a slot that keeps the entry with the most unsure tiles, since
+// those save the most work when hit again, and a slot that always takes the newest entry.
+pub struct TranspositionTable {
+    buckets: Vec<[Slot; 2]>,
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        TranspositionTable::with_bucket_bits(BUCKET_BITS)
+    }
+}
+
+impl TranspositionTable {
+    pub fn with_bucket_bits(bits: u32) -> Self {
+        TranspositionTable {
+            buckets: (0..1usize << bits).map(|_| Default::default()).collect(),
+        }
+    }
+
+    fn bucket(&self, hash: u64) -> &[Slot; 2] {
+        &self.buckets[hash as usize & (self.buckets.len() - 1)]
+    }
+
+    // whether the matrix has been proven impossible, if it's been seen at all. seen but not
+    // impossible means it's been explored without finishing, or is waiting to be further up
+    pub fn probe(&self, hash: u64) -> Option<bool> {
+        let data = self.bucket(hash).iter().find_map(|slot| slot.load(hash))?;
+        Some(data & IMPOSSIBLE != 0)
+    }
+
+    // unsure_count is a rough measure of how much work the matrix stands for
+    pub fn store(&self, hash: u64, unsure_count: usize, impossible: bool) {
+        let [deep, recent] = self.bucket(hash);
+        let mut data = OCCUPIED | unsure_count as u64;
+        if impossible {
+            data |= IMPOSSIBLE;
+        }
+
+        // already stored, only ever learn that it's impossible, never forget it
+        for slot in [deep, recent] {
+            if let Some(old_data) = slot.load(hash) {
+                if data & IMPOSSIBLE != 0 && old_data & IMPOSSIBLE == 0 {
+                    slot.save(hash, old_data | IMPOSSIBLE);
+                }
+                return;
+            }
+        }
+
+        let deep_data = deep.data.load(Ordering::Relaxed);
+        if deep_data & OCCUPIED == 0 || (deep_data & 0xFF) as usize <= unsure_count {
+            deep.save(hash, data);
+        } else {
+            recent.save(hash, data);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn impossible_is_learned_and_kept() {
+        let table = TranspositionTable::with_bucket_bits(4);
+        assert_eq!(table.probe(42), None);
+        table.store(42, 30, false);
+        assert_eq!(table.probe(42), Some(false));
+        table.store(42, 30, true);
+        assert_eq!(table.probe(42), Some(true));
+        table.store(42, 30, false);
+        assert_eq!(table.probe(42), Some(true));
+    }
+
+    #[test]
+    fn buckets_keep_the_most_work_and_the_newest() {
+        // one bucket, so every hash lands in the same two slots
+        let table = TranspositionTable::with_bucket_bits(0);
+        table.store(1, 40, false);
+        table.store(2, 10, false);
+        table.store(3, 20, true);
+        assert_eq!(table.probe(1), Some(false));
+        assert_eq!(table.probe(2), None);
+        assert_eq!(table.probe(3), Some(true));
+    }
+}
@@ -0,0 +1,159 @@
+use std::{fmt::{self, Write as _}, fs, io, path::Path, sync::{atomic::{AtomicU64, Ordering}, Mutex}};
+
+use crate::{contradiction::{Contradiction, Position}, room::Room, Tile};
+
+// plenty to see what's going on while still rendering to something readable
+pub const DEFAULT_NODE_LIMIT: usize = 500;
+
+// which structure a collapse was built from
+#[derive(Clone)]
+pub enum Structure {
+    // the matrix the search started from
+    Root,
+    // every neighbour of the monster walled off except its exit
+    MonsterExit { x: usize, y: usize, exit: (usize, usize) },
+    // treasure room around a chest, left and top being its top left ground tile
+    // no exit means the side walls already had one
+    Room { left: usize, top: usize, exit: Option<(usize, usize)> },
+    // a single unsure tile set to ground or wall
+    Cell { x: usize, y: usize, tile: Tile },
+}
+
+impl fmt::Display for Structure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Structure::Root => write!(f, "start"),
+            Structure::MonsterExit { x, y, exit } => {
+                write!(f, "monster at {}\nexits at {}", Position(*x, *y), Position(exit.0, exit.1))
+            },
+            Structure::Room { left, top, exit: Some(exit) } => {
+                write!(f, "room from {}\nexits at {}", Position(*left, *top), Position(exit.0, exit.1))
+            },
+            Structure::Room { left, top, exit: None } => write!(f, "room from {}\nexit already there", Position(*left, *top)),
+            Structure::Cell { x, y, tile } => write!(f, "{} {}", if tile == &Tile::Wall { "wall" } else { "ground" }, Position(*x, *y)),
+        }
+    }
+}
+
+impl Structure {
+    // every tile the collapse could have changed
+    pub fn cells(&self) -> Vec<(usize, usize)> {
+        match self {
+            Structure::Root => vec![],
+            Structure::MonsterExit { x, y, .. } => {
+                let (x, y) = (*x as i32, *y as i32);
+                [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)].into_iter()
+                    .filter(|&(x, y)| (0..8).contains(&x) && (0..8).contains(&y))
+                    .map(|(x, y)| (x as usize, y as usize))
+                    .collect()
+            },
+            Structure::Room { left, top, .. } => {
+                let room = Room { left: *left, top: *top };
+                room.inside().chain(room.side_walls()).collect()
+            },
+            Structure::Cell { x, y, .. } => vec![(*x, *y)],
+        }
+    }
+}
+
+// what became of a node by the time the search ended
+pub enum NodeStatus {
+    // passed every check, but the search never got around to it
+    Open,
+    // its own collapses got generated
+    Expanded,
+    // already seen through another path
+    Duplicate,
+    Contradiction(Contradiction),
+    Solved,
+}
+
+struct Node {
+    parent: Option<usize>,
+    structure: Structure,
+    board: String,
+    unsure_count: usize,
+    wall_count: usize,
+    status: NodeStatus,
+}
+
+// records the search tree as it gets explored, up to a limited number of nodes
+pub struct SearchTree {
+    node_limit: usize,
+    nodes: Mutex<Vec<Node>>,
+    // nodes left out after hitting the limit
+    dropped: AtomicU64,
+}
+
+impl SearchTree {
+    pub fn new(node_limit: usize) -> Self {
+        SearchTree {
+            node_limit,
+            nodes: Mutex::default(),
+            dropped: AtomicU64::default(),
+        }
+    }
+
+    // id of the new node, none if it didn't fit or its parent wasn't recorded either
+    pub fn add(&self, parent: Option<usize>, structure: Structure, matrix: &[Vec<Tile>], status: NodeStatus) -> Option<usize> {
+        if parent.is_none() && !matches!(structure, Structure::Root) {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            return None;
+        }
+        let mut nodes = self.nodes.lock().unwrap();
+        if nodes.len() >= self.node_limit {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            return None;
+        }
+        nodes.push(Node {
+            parent,
+            structure,
+            board: matrix.iter().map(|row| row.iter().map(Tile::symbol).collect::<String>()).collect::<Vec<_>>().join("\n"),
+            unsure_count: matrix.iter().flatten().filter(|tile| tile == &&Tile::Unsure).count(),
+            wall_count: matrix.iter().flatten().filter(|tile| tile == &&Tile::Wall).count(),
+            status,
+        });
+        Some(nodes.len() - 1)
+    }
+
+    pub fn set_status(&self, node: Option<usize>, status: NodeStatus) {
+        if let Some(node) = node {
+            self.nodes.lock().unwrap()[node].status = status;
+        }
+    }
+
+    // graphviz source, boards show up as tooltips when rendered to svg
+    pub fn to_dot(&self) -> String {
+        let nodes = self.nodes.lock().unwrap();
+        let dropped = self.dropped.load(Ordering::Relaxed);
+        let mut dot = String::from("digraph search {\n");
+        dot.push_str("    node [shape=box fontname=monospace fontsize=10];\n");
+        if dropped > 0 {
+            writeln!(dot, "    label=\"first {} nodes, {dropped} more left out\";", nodes.len()).unwrap();
+        }
+        for (id, node) in nodes.iter().enumerate() {
+            let (status, style) = match &node.status {
+                NodeStatus::Open => ("not explored".to_string(), "style=dashed"),
+                NodeStatus::Expanded => ("expanded".to_string(), ""),
+                NodeStatus::Duplicate => ("duplicate".to_string(), "color=gray fontcolor=gray"),
+                NodeStatus::Contradiction(contradiction) => (contradiction.to_string(), "color=red"),
+                NodeStatus::Solved => ("solved".to_string(), "color=green penwidth=2"),
+            };
+            let label = format!("{}\n{} unsure, {} walls\n{status}", node.structure, node.unsure_count, node.wall_count);
+            writeln!(dot, "    n{id} [label=\"{}\" tooltip=\"{}\" {style}];", escape(&label), escape(&node.board)).unwrap();
+            if let Some(parent) = node.parent {
+                writeln!(dot, "    n{parent} -> n{id};").unwrap();
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    pub fn write_dot(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_dot())
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
@@ -0,0 +1,111 @@
+use crate::{rules::Change, Tile};
+
+// one random key per tile state per position, xor-ed together to hash a matrix
+const KEYS: [[u64; 5]; 64] = generate_keys();
+
+const fn generate_keys() -> [[u64; 5]; 64] {
+    // splitmix64, the keys only need to look random and stay the same between runs
+    let mut state: u64 = 0x5EED_D15C_07EA_5EED;
+    let mut keys = [[0; 5]; 64];
+    let mut i = 0;
+    while i < 64 {
+        let mut j = 0;
+        while j < 5 {
+            state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            keys[i][j] = z ^ (z >> 31);
+            j += 1;
+        }
+        i += 1;
+    }
+    keys
+}
+
+fn key(x: usize, y: usize, tile: &Tile) -> u64 {
+    let state = match tile {
+        Tile::Unsure => 0,
+        Tile::Chest => 1,
+        Tile::Monster => 2,
+        Tile::Ground => 3,
+        Tile::Wall => 4,
+    };
+    KEYS[y * 8 + x][state]
+}
+
+pub fn hash(matrix: &[Vec<Tile>]) -> u64 {
+    let mut hash = 0;
+    for (y, row) in matrix.iter().enumerate() {
+        for (x, tile) in row.iter().enumerate() {
+            hash ^= key(x, y, tile);
+        }
+    }
+    hash
+}
+
+// hash of `after`, given the hash of `before` and the only tiles that can differ between them
+pub fn rehash(hash: u64, before: &[Vec<Tile>], after: &[Vec<Tile>], cells: impl IntoIterator<Item = (usize, usize)>) -> u64 {
+    let mut hash = hash;
+    let mut cells: Vec<(usize, usize)> = cells.into_iter().collect();
+    // a tile listed twice would get xor-ed back out
+    cells.sort_unstable();
+    cells.dedup();
+    for (x, y) in cells {
+        if before[y][x] != after[y][x] {
+            hash ^= key(x, y, &before[y][x]) ^ key(x, y, &after[y][x]);
+        }
+    }
+    hash
+}
+
+// hash of the matrix after the rules made their changes, in the order they made them
+pub fn apply(hash: u64, changes: &[Change]) -> u64 {
+    changes.iter().fold(hash, |hash, change| hash ^ key(change.x, change.y, &change.before) ^ key(change.x, change.y, &change.tile))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{collapse_random, parse_board, rules::{Puzzle, RuleSet}, tree::Structure};
+
+    const PUZZLE: &str = "
+         23243335
+        1???????!
+        4?O??????
+        0???????!
+        6????????
+        1!???????
+        6????????
+        2!??????!
+        5????????
+    ";
+
+    // a wrong incremental hash would make the search skip matrices it never saw
+    #[test]
+    fn incremental_hashes_match_full_ones() {
+        let (matrix, nums_columns, nums_rows) = parse_board(PUZZLE).unwrap();
+        let puzzle = Puzzle { nums_columns: &nums_columns, nums_rows: &nums_rows };
+        let start = hash(&matrix);
+        let (mut rooms, mut exits, mut rounds) = (0, 0, 0);
+        for (structure, mut collapse) in collapse_random(&matrix, &nums_columns, &nums_rows) {
+            match structure {
+                Structure::Room { .. } => rooms += 1,
+                Structure::MonsterExit { .. } => exits += 1,
+                _ => {},
+            }
+            let mut incremental = rehash(start, &matrix, &collapse, structure.cells());
+            assert_eq!(incremental, hash(&collapse), "after {structure}");
+            loop {
+                let changes = RuleSet::default().apply(&mut collapse, &puzzle);
+                if changes.is_empty() {
+                    break;
+                }
+                rounds += 1;
+                incremental = apply(incremental, &changes);
+                assert_eq!(incremental, hash(&collapse), "after propagating {structure}");
+            }
+        }
+        assert!(rooms > 0 && exits > 0 && rounds > 0);
+    }
+}